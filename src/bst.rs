@@ -10,6 +10,14 @@ impl<T: Copy + PartialOrd> Node<T> {
     pub fn new(value: T) -> Node<T> {
         Node { value: value, left: None, right: None }
     }
+
+    pub fn left(&self) -> Option<&Node<T>> {
+        self.left.as_deref()
+    }
+
+    pub fn right(&self) -> Option<&Node<T>> {
+        self.right.as_deref()
+    }
 }
 
 pub struct Tree<T: Copy + PartialOrd> {
@@ -272,6 +280,21 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_children() {
+        let mut tree = Tree::new();
+        tree.insert(2);
+        tree.insert(0);
+        tree.insert(1);
+
+        let root = tree.root.as_deref().unwrap();
+        assert!(root.right().is_none());
+        let left = root.left().unwrap();
+        assert_eq!(0, left.value);
+        assert!(left.left().is_none());
+        assert_eq!(1, left.right().unwrap().value);
+    }
+
     #[test]
     fn test_find() {
         let mut tree = Tree::new();
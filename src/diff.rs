@@ -0,0 +1,623 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+/// Kind of a single step of an edit script.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EditKind {
+    Equal,
+    Delete,
+    Insert
+}
+
+/// A run of edits of the same kind: `old[old]` is replaced by `new[new]`. For `Equal` both
+/// ranges have the same length, `Delete` has an empty `new` range at the position where the
+/// elements disappear and `Insert` an empty `old` range.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Edit {
+    pub kind: EditKind,
+    pub old: Range<usize>,
+    pub new: Range<usize>
+}
+
+/// Myers' greedy O(ND) diff, where D is the size of the shortest edit script. Keeps the
+/// furthest reaching paths of every step for the traceback, so it uses O((N + M) D) space.
+pub fn myers_diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut furthest = vec![0isize; 2 * max + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max as isize {
+        trace.push(furthest.clone());
+
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && furthest[index - 1] < furthest[index + 1]) {
+                furthest[index + 1]
+            }
+            else {
+                furthest[index - 1] + 1
+            };
+            let mut y = x - k;
+
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            furthest[index] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk the trace back; trace[d] holds the furthest paths before step d.
+    let mut steps = Vec::with_capacity((n + m) as usize);
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let previous = &trace[d as usize];
+        let k = x - y;
+        let index = (k + offset) as usize;
+        let previous_k = if k == -d || (k != d && previous[index - 1] < previous[index + 1]) { k + 1 } else { k - 1 };
+        let previous_x = previous[(previous_k + offset) as usize];
+        let previous_y = previous_x - previous_k;
+
+        while x > previous_x && y > previous_y {
+            steps.push(EditKind::Equal);
+            x -= 1;
+            y -= 1;
+        }
+
+        if previous_k == k + 1 {
+            steps.push(EditKind::Insert);
+        }
+        else {
+            steps.push(EditKind::Delete);
+        }
+
+        x = previous_x;
+        y = previous_y;
+    }
+
+    while x > 0 && y > 0 {
+        steps.push(EditKind::Equal);
+        x -= 1;
+        y -= 1;
+    }
+
+    steps.reverse();
+    coalesce(&steps)
+}
+
+/// Myers' diff with the linear space refinement: finds the middle snake of the shortest edit
+/// path with simultaneous forward and backward searches and recurses on both halves.
+/// O((N + M) D) time and O(N + M) space.
+pub fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let mut points = Vec::new();
+    find_path(old, new, Region { left: 0, top: 0, right: old.len() as isize, bottom: new.len() as isize }, &mut points);
+
+    let mut steps = Vec::with_capacity(old.len() + new.len());
+    let walk_diagonal = |x: &mut isize, y: &mut isize, x_end: isize, y_end: isize, steps: &mut Vec<EditKind>| {
+        while *x < x_end && *y < y_end && old[*x as usize] == new[*y as usize] {
+            steps.push(EditKind::Equal);
+            *x += 1;
+            *y += 1;
+        }
+    };
+
+    if points.is_empty() {
+        // Both sequences are empty.
+        return Vec::new();
+    }
+
+    for pair in points.windows(2) {
+        let (mut x, mut y) = pair[0];
+        let (x_end, y_end) = pair[1];
+
+        walk_diagonal(&mut x, &mut y, x_end, y_end, &mut steps);
+        match (x_end - x).cmp(&(y_end - y)) {
+            Ordering::Less => {
+                steps.push(EditKind::Insert);
+                y += 1;
+            },
+            Ordering::Greater => {
+                steps.push(EditKind::Delete);
+                x += 1;
+            },
+            Ordering::Equal => {}
+        }
+        walk_diagonal(&mut x, &mut y, x_end, y_end, &mut steps);
+    }
+
+    coalesce(&steps)
+}
+
+// Part of the edit graph between two points.
+#[derive(Clone, Copy)]
+struct Region {
+    left: isize,
+    top: isize,
+    right: isize,
+    bottom: isize
+}
+
+impl Region {
+    fn width(&self) -> isize {
+        self.right - self.left
+    }
+
+    fn height(&self) -> isize {
+        self.bottom - self.top
+    }
+
+    fn delta(&self) -> isize {
+        self.width() - self.height()
+    }
+}
+
+// Appends points of the shortest path through the box; consecutive points are joined by at
+// most one insertion or deletion and a diagonal.
+fn find_path<T: PartialEq>(old: &[T], new: &[T], area: Region, points: &mut Vec<(isize, isize)>) {
+    let (start, finish) = match middle_snake(old, new, area) {
+        Some(snake) => snake,
+        None => {
+            if points.last() != Some(&(area.left, area.top)) {
+                points.push((area.left, area.top));
+            }
+            return;
+        }
+    };
+
+    let head = Region { left: area.left, top: area.top, right: start.0, bottom: start.1 };
+    let tail = Region { left: finish.0, top: finish.1, right: area.right, bottom: area.bottom };
+
+    if head.width() + head.height() > 0 {
+        find_path(old, new, head, points);
+    }
+    if points.last() != Some(&start) {
+        points.push(start);
+    }
+    if points.last() != Some(&finish) {
+        points.push(finish);
+    }
+
+    if tail.width() + tail.height() > 0 {
+        find_path(old, new, tail, points);
+    }
+}
+
+type Point = (isize, isize);
+
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T], area: Region) -> Option<(Point, Point)> {
+    let size = area.width() + area.height();
+    if size == 0 {
+        return None;
+    }
+
+    let max = (size + 1) / 2;
+    let offset = max + 1;
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+    forward[(1 + offset) as usize] = area.left;
+    backward[(1 + offset) as usize] = area.bottom;
+    let odd = area.delta() % 2 != 0;
+
+    for d in 0..=max {
+        // Forward search, from the top left corner.
+        for k in (-d..=d).rev().step_by(2) {
+            let c = k - area.delta();
+            let index = (k + offset) as usize;
+            let (previous_x, mut x) = if k == -d || (k != d && forward[index - 1] < forward[index + 1]) {
+                (forward[index + 1], forward[index + 1])
+            }
+            else {
+                (forward[index - 1], forward[index - 1] + 1)
+            };
+
+            let mut y = area.top + (x - area.left) - k;
+            let previous_y = if d == 0 || x != previous_x { y } else { y - 1 };
+
+            while x < area.right && y < area.bottom && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index] = x;
+
+            if odd && (-(d - 1)..=d - 1).contains(&c) && y >= backward[(c + offset) as usize] {
+                return Some(((previous_x, previous_y), (x, y)));
+            }
+        }
+
+        // Backward search, from the bottom right corner.
+        for c in (-d..=d).rev().step_by(2) {
+            let k = c + area.delta();
+            let index = (c + offset) as usize;
+            let (previous_y, mut y) = if c == -d || (c != d && backward[index - 1] > backward[index + 1]) {
+                (backward[index + 1], backward[index + 1])
+            }
+            else {
+                (backward[index - 1], backward[index - 1] - 1)
+            };
+
+            let mut x = area.left + (y - area.top) + k;
+            let previous_x = if d == 0 || y != previous_y { x } else { x + 1 };
+
+            while x > area.left && y > area.top && old[x as usize - 1] == new[y as usize - 1] {
+                x -= 1;
+                y -= 1;
+            }
+            backward[index] = y;
+
+            if !odd && (-d..=d).contains(&k) && x <= forward[(k + offset) as usize] {
+                return Some(((x, y), (previous_x, previous_y)));
+            }
+        }
+    }
+
+    None
+}
+
+fn coalesce(steps: &[EditKind]) -> Vec<Edit> {
+    let mut result: Vec<Edit> = Vec::new();
+    let (mut x, mut y) = (0, 0);
+
+    for &kind in steps {
+        let (next_x, next_y) = match kind {
+            EditKind::Equal => (x + 1, y + 1),
+            EditKind::Delete => (x + 1, y),
+            EditKind::Insert => (x, y + 1)
+        };
+
+        match result.last_mut() {
+            Some(last) if last.kind == kind => {
+                last.old.end = next_x;
+                last.new.end = next_y;
+            },
+            _ => result.push(Edit { kind, old: x..next_x, new: y..next_y })
+        }
+
+        x = next_x;
+        y = next_y;
+    }
+
+    result
+}
+
+/// Settings of `unified_diff`.
+pub struct UnifiedDiffOptions<'a> {
+    /// Number of unchanged lines shown around every change.
+    pub context: usize,
+    pub old_name: &'a str,
+    pub new_name: &'a str
+}
+
+impl<'a> Default for UnifiedDiffOptions<'a> {
+    fn default() -> Self {
+        UnifiedDiffOptions { context: 3, old_name: "a", new_name: "b" }
+    }
+}
+
+/// Renders the line diff of two texts in the unified format. Returns an empty string if the
+/// texts are equal.
+pub fn unified_diff(old: &str, new: &str, options: &UnifiedDiffOptions) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = diff(&old_lines, &new_lines);
+    let mut result = String::new();
+
+    if edits.iter().all(|edit| edit.kind == EditKind::Equal) {
+        return result;
+    }
+
+    writeln!(result, "--- {}", options.old_name).unwrap();
+    writeln!(result, "+++ {}", options.new_name).unwrap();
+
+    // Group changes separated by at most 2 * context equal lines into hunks.
+    let mut i = 0;
+    while i < edits.len() {
+        if edits[i].kind == EditKind::Equal {
+            i += 1;
+            continue;
+        }
+
+        let first = i;
+        let mut last = i;
+        let mut j = i + 1;
+        while j < edits.len() {
+            if edits[j].kind != EditKind::Equal {
+                last = j;
+            }
+            else if edits[j].old.len() > 2 * options.context || j + 1 == edits.len() {
+                break;
+            }
+
+            j += 1;
+        }
+
+        let leading = if first > 0 { edits[first - 1].old.len().min(options.context) } else { 0 };
+        let trailing = if last + 1 < edits.len() { edits[last + 1].old.len().min(options.context) } else { 0 };
+        let old_range = edits[first].old.start - leading..edits[last].old.end + trailing;
+        let new_range = edits[first].new.start - leading..edits[last].new.end + trailing;
+
+        writeln!(result, "@@ -{} +{} @@", hunk_range(&old_range), hunk_range(&new_range)).unwrap();
+        if leading > 0 {
+            write_lines(&mut result, ' ', &old_lines[old_range.start..edits[first].old.start]);
+        }
+        for edit in &edits[first..=last] {
+            match edit.kind {
+                EditKind::Equal => write_lines(&mut result, ' ', &old_lines[edit.old.clone()]),
+                EditKind::Delete => write_lines(&mut result, '-', &old_lines[edit.old.clone()]),
+                EditKind::Insert => write_lines(&mut result, '+', &new_lines[edit.new.clone()])
+            }
+        }
+        if trailing > 0 {
+            write_lines(&mut result, ' ', &old_lines[edits[last].old.end..old_range.end]);
+        }
+
+        i = last + 1;
+    }
+
+    result
+}
+
+// Line numbers are 1-based; an empty range points at the line before it.
+fn hunk_range(range: &Range<usize>) -> String {
+    match range.len() {
+        0 => format!("{},0", range.start),
+        1 => format!("{}", range.start + 1),
+        length => format!("{},{}", range.start + 1, length)
+    }
+}
+
+fn write_lines(result: &mut String, prefix: char, lines: &[&str]) {
+    for line in lines {
+        result.push(prefix);
+        result.push_str(line);
+        if !line.ends_with('\n') {
+            result.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PatchError {
+    /// The hunk header at the given (1-based) patch line cannot be parsed.
+    MalformedHeader(usize),
+    /// The patch line at the given position does not start with ' ', '-' or '+'.
+    MalformedLine(usize),
+    /// The context or deleted line at the given patch line differs from the text.
+    Mismatch(usize),
+    /// A hunk starts before the end of the previous one or past the end of the text.
+    InvalidPosition(usize),
+    /// The hunk with the header at the given patch line has more or fewer lines than the
+    /// header says, for example because the patch was cut off.
+    HunkLength(usize)
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::MalformedHeader(line) => write!(f, "malformed hunk header at line {}", line),
+            PatchError::MalformedLine(line) => write!(f, "malformed patch line {}", line),
+            PatchError::Mismatch(line) => write!(f, "patch line {} does not match the text", line),
+            PatchError::InvalidPosition(line) => write!(f, "hunk at line {} is out of order or out of range", line),
+            PatchError::HunkLength(line) => write!(f, "hunk at line {} does not match the lengths in its header", line)
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Applies a unified diff produced by `unified_diff` to the old text. Context lines must match
+/// exactly, and every hunk must have as many lines as its header says.
+pub fn apply_patch(old: &str, patch: &str) -> Result<String, PatchError> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let patch_lines: Vec<&str> = patch.split_inclusive('\n').collect();
+    let mut result = String::with_capacity(old.len());
+    let mut position = 0;
+    let mut i = 0;
+
+    while i < patch_lines.len() {
+        let line = patch_lines[i];
+        if !line.starts_with("@@") {
+            // File headers and other preamble.
+            i += 1;
+            continue;
+        }
+
+        let header = i + 1;
+        let (start, old_length, new_length) = parse_hunk_header(line).ok_or(PatchError::MalformedHeader(header))?;
+        let start = if old_length == 0 { start } else { start.saturating_sub(1) };
+        if start < position || start > old_lines.len() {
+            return Err(PatchError::InvalidPosition(header));
+        }
+
+        old_lines[position..start].iter().for_each(|line| result.push_str(line));
+        position = start;
+        i += 1;
+
+        // The header lengths tell where the hunk ends; a cut off or padded hunk is an error.
+        let (mut old_count, mut new_count) = (0, 0);
+        while old_count < old_length || new_count < new_length {
+            if i == patch_lines.len() || patch_lines[i].starts_with("@@") {
+                return Err(PatchError::HunkLength(header));
+            }
+
+            let line_number = i + 1;
+            let mut content = &patch_lines[i][1.min(patch_lines[i].len())..];
+            if patch_lines.get(i + 1).is_some_and(|next| next.starts_with('\\')) {
+                content = content.strip_suffix('\n').unwrap_or(content);
+                i += 1;
+            }
+
+            match patch_lines[line_number - 1].chars().next() {
+                Some(' ') | Some('-') => {
+                    if old_lines.get(position) != Some(&content) {
+                        return Err(PatchError::Mismatch(line_number));
+                    }
+                    if patch_lines[line_number - 1].starts_with(' ') {
+                        result.push_str(content);
+                        new_count += 1;
+                    }
+                    position += 1;
+                    old_count += 1;
+                },
+                Some('+') => {
+                    result.push_str(content);
+                    new_count += 1;
+                },
+                _ => return Err(PatchError::MalformedLine(line_number))
+            }
+
+            if old_count > old_length || new_count > new_length {
+                return Err(PatchError::HunkLength(header));
+            }
+            i += 1;
+        }
+
+        // Only a new hunk or file headers may follow a complete hunk.
+        if patch_lines.get(i).is_some_and(|next| next.starts_with([' ', '-', '+']) && !next.starts_with("--- ") && !next.starts_with("+++ ")) {
+            return Err(PatchError::HunkLength(header));
+        }
+    }
+
+    old_lines[position..].iter().for_each(|line| result.push_str(line));
+    Ok(result)
+}
+
+// Parses "@@ -start[,length] +start[,length] @@" into the old start and both lengths.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = line.strip_prefix("@@ -")?.split(' ');
+    let (start, old_length) = parse_hunk_range(ranges.next()?)?;
+    let (_, new_length) = parse_hunk_range(ranges.next()?.strip_prefix('+')?)?;
+
+    Some((start, old_length, new_length))
+}
+
+fn parse_hunk_range(range: &str) -> Option<(usize, usize)> {
+    let mut parts = range.split(',');
+    let start = parts.next()?.parse().ok()?;
+    let length = match parts.next() {
+        Some(length) => length.parse().ok()?,
+        None => 1
+    };
+
+    Some((start, length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic::longest_common_subsequence;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    fn check_edits<T: PartialEq + std::fmt::Debug>(old: &[T], new: &[T], edits: &[Edit]) {
+        let (mut x, mut y) = (0, 0);
+        for edit in edits {
+            assert_eq!((x, y), (edit.old.start, edit.new.start));
+            match edit.kind {
+                EditKind::Equal => assert_eq!(&old[edit.old.clone()], &new[edit.new.clone()]),
+                EditKind::Delete => assert!(edit.new.is_empty()),
+                EditKind::Insert => assert!(edit.old.is_empty())
+            }
+            x = edit.old.end;
+            y = edit.new.end;
+        }
+        assert_eq!((old.len(), new.len()), (x, y));
+
+        let changes: usize = edits.iter().filter(|edit| edit.kind != EditKind::Equal).map(|edit| edit.old.len() + edit.new.len()).sum();
+        assert_eq!(old.len() + new.len() - 2 * longest_common_subsequence(old, new).len(), changes);
+    }
+
+    #[test]
+    fn diff_test() {
+        let old: Vec<char> = "abcabba".chars().collect();
+        let new: Vec<char> = "cbabac".chars().collect();
+
+        for edits in [myers_diff(&old, &new), diff(&old, &new)] {
+            check_edits(&old, &new, &edits);
+        }
+
+        assert!(diff::<u8>(&[], &[]).is_empty());
+        assert_eq!(vec![Edit { kind: EditKind::Insert, old: 0..0, new: 0..2 }], diff(b"", b"ab"));
+        assert_eq!(vec![Edit { kind: EditKind::Delete, old: 0..2, new: 0..0 }], myers_diff(b"ab", b""));
+    }
+
+    #[test]
+    fn diff_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(41);
+
+        for _ in 0..300 {
+            let old: Vec<u8> = (0..rng.gen_range(0..40)).map(|_| rng.gen_range(b'a'..b'd')).collect();
+            let new: Vec<u8> = (0..rng.gen_range(0..40)).map(|_| rng.gen_range(b'a'..b'd')).collect();
+
+            check_edits(&old, &new, &myers_diff(&old, &new));
+            check_edits(&old, &new, &diff(&old, &new));
+        }
+    }
+
+    #[test]
+    fn unified_diff_test() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk";
+        let options = UnifiedDiffOptions { context: 1, old_name: "old.txt", new_name: "new.txt" };
+
+        let expected = "\
+--- old.txt
++++ new.txt
+@@ -1,3 +1,3 @@
+ a
+-b
++B
+ c
+@@ -10 +10,2 @@
+ j
++k
+\\ No newline at end of file
+";
+        let patch = unified_diff(old, new, &options);
+        assert_eq!(expected, patch);
+        assert_eq!(Ok(String::from(new)), apply_patch(old, &patch));
+
+        assert_eq!("", unified_diff(old, old, &options));
+    }
+
+    #[test]
+    fn patch_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(141);
+
+        for _ in 0..200 {
+            let old: String = (0..rng.gen_range(0..30)).map(|_| ["x\n", "y\n", "z\n", "w"][rng.gen_range(0..4)]).collect();
+            let new: String = (0..rng.gen_range(0..30)).map(|_| ["x\n", "y\n", "z\n", "w"][rng.gen_range(0..4)]).collect();
+            let options = UnifiedDiffOptions { context: rng.gen_range(0..4), ..UnifiedDiffOptions::default() };
+
+            let patch = unified_diff(&old, &new, &options);
+            assert_eq!(Ok(new), apply_patch(&old, &patch));
+        }
+    }
+
+    #[test]
+    fn patch_errors_test() {
+        assert_eq!(Err(PatchError::Mismatch(2)), apply_patch("a\n", "@@ -1 +1 @@\n-b\n+c\n"));
+        assert_eq!(Err(PatchError::MalformedHeader(1)), apply_patch("a\n", "@@ -x +1 @@\n"));
+        assert_eq!(Err(PatchError::InvalidPosition(1)), apply_patch("a\n", "@@ -5 +5 @@\n-a\n"));
+
+        // Cut off in the middle of a hunk, or with extra lines in it.
+        let old = "a\nb\nc\nd\n";
+        assert_eq!(Ok(String::from("a\nB\nc\nd\n")), apply_patch(old, "@@ -2 +2 @@\n-b\n+B\n"));
+        assert_eq!(Err(PatchError::HunkLength(1)), apply_patch(old, "@@ -2 +2 @@\n-b\n"));
+        assert_eq!(Err(PatchError::HunkLength(1)), apply_patch(old, "@@ -2,2 +2,2 @@\n-b\n+B\n"));
+        assert_eq!(Err(PatchError::HunkLength(1)), apply_patch(old, "@@ -2 +2 @@\n-b\n+B\n+C\n"));
+        assert_eq!(Err(PatchError::HunkLength(1)), apply_patch(old, "@@ -2 +2,2 @@\n-b\n+B\n@@ -4 +5 @@\n-d\n+D\n"));
+        assert_eq!(Err(PatchError::MalformedHeader(1)), apply_patch(old, "@@ -2 @@\n-b\n"));
+
+        // File headers of the next file may follow a complete hunk.
+        let patch = "--- a\n+++ a\n@@ -2 +2 @@\n-b\n+B\n--- b\n+++ b\n";
+        assert_eq!(Ok(String::from("a\nB\nc\nd\n")), apply_patch(old, patch));
+    }
+}
@@ -1,27 +1,146 @@
-use std::{fmt::Write, cmp::Ordering, mem::swap};
+pub mod alignment;
+pub mod matrix;
+pub mod matrix_chain;
+pub mod number;
+pub mod optimal_bst;
 
-pub fn cut_rod(prices: &Vec<f64>) -> (f64, Vec<usize>) {
-    let mut profit = vec![0.0; prices.len() + 1];
-    let mut best_cuts = vec![0; prices.len()];
+use std::{fmt::{self, Write}, cmp::Ordering, mem::swap};
+use std::borrow::Cow;
+use std::ops::{Add, Mul};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+use self::matrix::Matrix;
+use self::number::Number;
+
+pub fn cut_rod<T: Number>(prices: &[T]) -> (T, Vec<usize>) {
+    let (profit, best_cuts) = cut_rod_table(prices, prices.len());
+
+    (profit[prices.len()], cut_rod_pieces(&best_cuts, prices.len()))
+}
+
+/// Rod cutting for many rod lengths at once, sharing one table up to the longest rod. Pieces
+/// are at most `prices.len()` long. Returns the profit and the cuts for every length.
+pub fn cut_rods<T: Number>(prices: &[T], lengths: &[usize]) -> Vec<(T, Vec<usize>)> {
+    let longest = lengths.iter().copied().max().unwrap_or(0);
+    assert!(!prices.is_empty() || longest == 0, "rods cannot be cut without prices");
+
+    let (profit, best_cuts) = cut_rod_table(prices, longest);
+
+    lengths.iter().map(|&length| (profit[length], cut_rod_pieces(&best_cuts, length))).collect()
+}
+
+// Best profits for all rods up to `length` and the first piece of every best cut.
+fn cut_rod_table<T: Number>(prices: &[T], length: usize) -> (Vec<T>, Vec<usize>) {
+    let mut profit = vec![T::zero(); length + 1];
+    let mut best_cuts = vec![0; length];
     
-    for i in 0..prices.len() {
-        profit[i + 1] = f64::MIN;
-        for j in 0..(i + 1) {
+    for i in 0..length {
+        profit[i + 1] = prices[0] + profit[i];
+        best_cuts[i] = 1;
+        for j in 1..(i + 1).min(prices.len()) {
             if profit[i + 1] < prices[j] + profit[i - j] {
                 profit[i + 1] = prices[j] + profit[i - j];
                 best_cuts[i] = j + 1;
             }
         }
     }
-    
+
+    (profit, best_cuts)
+}
+
+fn cut_rod_pieces(best_cuts: &[usize], length: usize) -> Vec<usize> {
     let mut cuts = Vec::new();
-    let mut remaining = prices.len();
+    let mut remaining = length;
     while remaining > 0 {
         cuts.push(best_cuts[remaining - 1]);
         remaining -= best_cuts[remaining- 1];
     }
     
-    (profit[prices.len()], cuts)
+    cuts
+}
+
+/// Rod cutting where every cut costs `cut_cost` (CLRS exercise 15.1-3).
+pub fn cut_rod_with_cost<T: Number>(prices: &[T], cut_cost: T) -> (T, Vec<usize>) {
+    let mut profit = vec![T::zero(); prices.len() + 1];
+    let mut best_cuts = vec![0; prices.len()];
+
+    for i in 0..prices.len() {
+        // Selling the rod as it is, or a first piece of length j + 1 and a cut.
+        profit[i + 1] = prices[i];
+        best_cuts[i] = i + 1;
+        for j in 0..i {
+            let with_cut = prices[j] + profit[i - j];
+            if with_cut > profit[i + 1] + cut_cost {
+                profit[i + 1] = with_cut - cut_cost;
+                best_cuts[i] = j + 1;
+            }
+        }
+    }
+
+    (profit[prices.len()], cut_rod_pieces(&best_cuts, prices.len()))
+}
+
+/// Top-down version of `cut_rod` with memoization (CLRS MEMOIZED-CUT-ROD). The recursion is
+/// kept on an explicit stack, so long rods do not overflow the call stack.
+pub fn cut_rod_memoized<T: Number>(prices: &[T]) -> (T, Vec<usize>) {
+    // A length being solved, with the next first piece to try and the best choice so far.
+    struct Frame<T> {
+        length: usize,
+        piece: usize,
+        best: Option<(T, usize)>
+    }
+
+    let n = prices.len();
+    // The best profit and first piece for every length, filled in on the first visit.
+    let mut memo: Vec<Option<(T, usize)>> = vec![None; n];
+    let mut stack = Vec::new();
+    if n > 0 {
+        stack.push(Frame { length: n, piece: 0, best: None });
+    }
+
+    while let Some(frame) = stack.last_mut() {
+        if frame.piece == frame.length {
+            memo[frame.length - 1] = frame.best;
+            stack.pop();
+            continue;
+        }
+
+        let rest = frame.length - frame.piece - 1;
+        let solved = if rest == 0 { Some(T::zero()) } else { memo[rest - 1].map(|(profit, _)| profit) };
+        match solved {
+            Some(rest_profit) => {
+                let profit = prices[frame.piece] + rest_profit;
+                if frame.best.is_none_or(|(best, _)| best < profit) {
+                    frame.best = Some((profit, frame.piece + 1));
+                }
+                frame.piece += 1;
+            },
+            None => stack.push(Frame { length: rest, piece: 0, best: None })
+        }
+    }
+
+    let profit = if n == 0 { T::zero() } else { memo[n - 1].unwrap().0 };
+    let best_cuts: Vec<usize> = memo.iter().map(|entry| entry.map_or(0, |(_, cut)| cut)).collect();
+
+    (profit, cut_rod_pieces(&best_cuts, n))
+}
+
+/// Rod cutting with at most `limits[j]` pieces of length `j + 1`. The rest of the rod that
+/// cannot be sold is scrap. Solved as a bounded knapsack over the piece lengths.
+pub fn cut_rod_bounded<T: Number>(prices: &[T], limits: &[usize]) -> (T, Vec<usize>) {
+    assert_eq!(prices.len(), limits.len(), "every length needs a limit");
+
+    let pieces: Vec<KnapsackItem<T>> = prices.iter().enumerate()
+        .map(|(j, &value)| KnapsackItem { value, weight: j + 1 })
+        .collect();
+    let result = bounded_knapsack(&pieces, limits, prices.len());
+
+    let mut cuts = Vec::new();
+    for (piece, &quantity) in result.items.iter().zip(&result.quantities) {
+        cuts.extend(std::iter::repeat_n(piece.weight, quantity));
+    }
+
+    (result.total_value, cuts)
 }
 
 #[inline]
@@ -29,6 +148,7 @@ pub fn idx(x: usize, y: usize, n: usize) -> usize {
     x + y * n
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct MatrixSize {
     pub rows: usize,
     pub cols: usize
@@ -37,10 +157,23 @@ pub struct MatrixSize {
 pub struct MultiplicationOrder {
     pub multiplications: usize,
     pub matrices_count: usize,
-    pub order: Vec<usize>
+    /// Splits of all products of matrices `left..=right`, at `idx(left, right, matrices_count)`,
+    /// as computed by `matrix_mul`. Empty for `matrix_chain::hu_shing`, which does not solve
+    /// every subchain.
+    #[deprecated(note = "use `split`, which also works for orders found by `hu_shing`")]
+    pub order: Vec<usize>,
+    // Last matrix of the left factor of the product of matrices left..=right, for the
+    // products in the order only.
+    splits: HashMap<(usize, usize), usize>
 }
 
 impl MultiplicationOrder {
+    /// Index of the last matrix of the left factor when multiplying matrices `left..=right`.
+    /// `None` if that product is not computed by this order.
+    pub fn split(&self, left: usize, right: usize) -> Option<usize> {
+        self.splits.get(&(left, right)).copied()
+    }
+
     pub fn to_string(&self) -> String {
         enum StackOperation {
             WriteCharacter(&'static str),
@@ -49,7 +182,10 @@ impl MultiplicationOrder {
 
         let mut stack = Vec::new();
         let mut result = String::new();
-        
+        if self.matrices_count == 0 {
+            return result;
+        }
+
         stack.push(StackOperation::WriteMultiplication(0, self.matrices_count - 1));
 
         while let Some(operation) = stack.pop() {
@@ -63,7 +199,7 @@ impl MultiplicationOrder {
                     }
                     else {
                         // We are using stack, so the order has to be reversed
-                        let split = self.order[idx(left, right, self.matrices_count)];
+                        let split = self.splits[&(left, right)];
                         stack.push(StackOperation::WriteCharacter(")"));
                         stack.push(StackOperation::WriteMultiplication(split + 1, right));
                         stack.push(StackOperation::WriteCharacter("*"));
@@ -78,36 +214,136 @@ impl MultiplicationOrder {
     }
 }
 
-pub fn matrix_mul(matrices: &Vec<MatrixSize>) -> MultiplicationOrder {
+/// Optimal order of multiplication of a chain of matrices, found with the O(n³) dynamic
+/// programming. Costs are checked for overflow. See `matrix_chain::hu_shing` for long chains.
+pub fn matrix_mul(matrices: &[MatrixSize]) -> Result<MultiplicationOrder, MatrixChainError> {
+    validate_chain(matrices)?;
     let n = matrices.len();
-    let mut multiplications = vec![0; n * n];
+    // None marks a product whose cost overflows.
+    let mut multiplications = vec![Some(0usize); n * n];
     let mut order = vec![0; n * n];
     
     // Size of the matrices
-    for i in 1..matrices.len() {
-        for j in 0..matrices.len() - i {
+    for i in 1..n {
+        for j in 0..n - i {
             let index = idx(j, j + i, n);
-            multiplications[index] = usize::MAX;
+            multiplications[index] = None;
             for k in j..j + i {
-                let this_split_cost = multiplications[idx(j, k, n)] + multiplications[idx(k + 1, j + i, n)] + matrices[j].rows * matrices[k].cols * matrices[j + i].cols;
-                if this_split_cost < multiplications[index] {
-                    multiplications[index] = this_split_cost;
-                    order[index] = k;
+                let this_split_cost = multiplications[idx(j, k, n)]
+                    .zip(multiplications[idx(k + 1, j + i, n)])
+                    .and_then(|(left, right)| left.checked_add(right))
+                    .and_then(|cost| matrices[j].rows.checked_mul(matrices[k].cols)
+                        .and_then(|product| product.checked_mul(matrices[j + i].cols))
+                        .and_then(|product| cost.checked_add(product)));
+
+                if let Some(cost) = this_split_cost {
+                    if multiplications[index].is_none_or(|best| cost < best) {
+                        multiplications[index] = Some(cost);
+                        order[index] = k;
+                    }
                 }
             }
         }
     }
-    
-    MultiplicationOrder { 
-        multiplications: multiplications[idx(0, n - 1, n)],
-        matrices_count: n, 
-        order: order 
+
+    // The splits of overflowing products are not set, so the order cannot be followed.
+    let total = if n == 0 { 0 } else { multiplications[idx(0, n - 1, n)].ok_or(MatrixChainError::Overflow)? };
+    let mut splits = HashMap::new();
+    let mut stack = if n > 1 { vec![(0, n - 1)] } else { Vec::new() };
+    while let Some((left, right)) = stack.pop() {
+        let split = order[idx(left, right, n)];
+        splits.insert((left, right), split);
+        if split > left {
+            stack.push((left, split));
+        }
+        if split + 1 < right {
+            stack.push((split + 1, right));
+        }
+    }
+
+    #[allow(deprecated)]
+    Ok(MultiplicationOrder {
+        multiplications: total,
+        matrices_count: n,
+        order,
+        splits
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MatrixChainError {
+    /// The number of columns of the matrix at the index differs from the number of rows of
+    /// the next one.
+    DimensionMismatch(usize),
+    /// The multiplication order was computed for a different number of matrices.
+    CountMismatch { expected: usize, actual: usize },
+    /// The chain has no matrices, so its product is not defined.
+    Empty,
+    /// The number of scalar multiplications does not fit in `usize`.
+    Overflow
+}
+
+impl fmt::Display for MatrixChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatrixChainError::DimensionMismatch(index) => write!(f, "matrices {} and {} cannot be multiplied", index, index + 1),
+            MatrixChainError::CountMismatch { expected, actual } => write!(f, "expected {} matrices, got {}", expected, actual),
+            MatrixChainError::Empty => write!(f, "the chain has no matrices"),
+            MatrixChainError::Overflow => write!(f, "the number of multiplications overflows")
+        }
     }
 }
 
-pub fn knapsack<'a>(items: &'a Vec<KnapsackItem>, capacity: usize) -> OptimalKnapsack<'a> {
-    let mut best_previous_values = vec![0.0; capacity + 1];
-    let mut best_current_values = vec![0.0; capacity + 1];
+impl std::error::Error for MatrixChainError {}
+
+/// Checks that adjacent matrices of the chain can be multiplied.
+pub fn validate_chain(matrices: &[MatrixSize]) -> Result<(), MatrixChainError> {
+    match matrices.windows(2).position(|pair| pair[0].cols != pair[1].rows) {
+        Some(index) => Err(MatrixChainError::DimensionMismatch(index)),
+        None => Ok(())
+    }
+}
+
+/// Multiplies the chain of matrices in the order found by `matrix_mul` or `hu_shing`.
+pub fn execute<T>(order: &MultiplicationOrder, matrices: &[Matrix<T>]) -> Result<Matrix<T>, MatrixChainError>
+    where T: Copy + From<u8> + Add<Output=T> + Mul<Output=T> {
+    if matrices.len() != order.matrices_count {
+        return Err(MatrixChainError::CountMismatch { expected: order.matrices_count, actual: matrices.len() });
+    }
+    if matrices.is_empty() {
+        return Err(MatrixChainError::Empty);
+    }
+
+    let sizes: Vec<MatrixSize> = matrices.iter().map(|matrix| matrix.size()).collect();
+    validate_chain(&sizes)?;
+
+    fn multiply_range<'a, T>(order: &MultiplicationOrder, matrices: &'a [Matrix<T>], left: usize, right: usize) -> Cow<'a, Matrix<T>>
+        where T: Copy + From<u8> + Add<Output=T> + Mul<Output=T> {
+        if left == right {
+            return Cow::Borrowed(&matrices[left]);
+        }
+
+        let split = order.splits[&(left, right)];
+        let left_product = multiply_range(order, matrices, left, split);
+        let right_product = multiply_range(order, matrices, split + 1, right);
+        Cow::Owned(left_product.multiply(&right_product))
+    }
+
+    Ok(multiply_range(order, matrices, 0, matrices.len() - 1).into_owned())
+}
+
+pub fn knapsack<T: Number>(items: &[KnapsackItem<T>], capacity: usize) -> OptimalKnapsack<'_, T> {
+    let (total_weight, best_value, chosen) = knapsack_choose(items, capacity);
+    let quantities = vec![1; chosen.len()];
+    let items = chosen.into_iter().map(|i| &items[i]).collect();
+
+    OptimalKnapsack { total_weight, total_value: best_value, items, quantities, fractions: Vec::new() }
+}
+
+// 0/1 knapsack; returns the total weight and value and the indices of the chosen items.
+fn knapsack_choose<T: Number>(items: &[KnapsackItem<T>], capacity: usize) -> (usize, T, Vec<usize>) {
+    let mut best_previous_values = vec![T::zero(); capacity + 1];
+    let mut best_current_values = vec![T::zero(); capacity + 1];
     let mut chosen_items = vec![false; items.len() * capacity];
 
     for i in 0..items.len() {
@@ -128,12 +364,12 @@ pub fn knapsack<'a>(items: &'a Vec<KnapsackItem>, capacity: usize) -> OptimalKna
     }    
     
     let (total_weight, best_value) = knapsack_find_best_value(&best_previous_values);
-    let items = knapsack_find_items(items, &chosen_items, capacity, total_weight);
+    let chosen = knapsack_find_items(items, &chosen_items, capacity, total_weight);
         
-    OptimalKnapsack { total_weight: total_weight, total_value: best_value, items: items }
+    (total_weight, best_value, chosen)
 }
 
-fn knapsack_find_best_value(values: &Vec<f64>) -> (usize, f64) {
+fn knapsack_find_best_value<T: Number>(values: &[T]) -> (usize, T) {
     let (index, best_value) = values.iter().enumerate()
         .max_by(|(_, x), (_, y)| x.partial_cmp(y).unwrap_or(Ordering::Equal))
         .unwrap();
@@ -141,18 +377,18 @@ fn knapsack_find_best_value(values: &Vec<f64>) -> (usize, f64) {
     (index, *best_value)
 }
 
-fn knapsack_find_items<'a>(items: &'a Vec<KnapsackItem>, chosen_items: &Vec<bool>, capacity: usize, total_weight: usize) -> Vec<&'a KnapsackItem> {
+fn knapsack_find_items<T>(items: &[KnapsackItem<T>], chosen_items: &[bool], capacity: usize, total_weight: usize) -> Vec<usize> {
     let mut current_weight = total_weight;
     let mut result = Vec::new();
 
     for i in (0..items.len()).rev() {
+        if current_weight == 0 {
+            break;
+        }
+
         if chosen_items[(idx(current_weight - 1, i, capacity))] {
-            result.push(&items[i]);
+            result.push(i);
             current_weight -= items[i].weight;
-            
-            if current_weight == 0 {
-                break;
-            }
         }
     }
 
@@ -160,25 +396,342 @@ fn knapsack_find_items<'a>(items: &'a Vec<KnapsackItem>, chosen_items: &Vec<bool
     result
 }
 
+/// Knapsack with an unlimited number of copies of every item. Items must have a positive
+/// weight. O(n W) time, O(W) space.
+pub fn unbounded_knapsack<T: Number>(items: &[KnapsackItem<T>], capacity: usize) -> OptimalKnapsack<'_, T> {
+    assert!(items.iter().all(|item| item.weight > 0), "items must have a positive weight");
+
+    // The best value for every capacity and the item added last (None: same as capacity - 1).
+    let mut best_values = vec![T::zero(); capacity + 1];
+    let mut last_items = vec![None; capacity + 1];
+
+    for j in 1..=capacity {
+        best_values[j] = best_values[j - 1];
+
+        for (i, item) in items.iter().enumerate() {
+            if item.weight <= j && best_values[j - item.weight] + item.value > best_values[j] {
+                best_values[j] = best_values[j - item.weight] + item.value;
+                last_items[j] = Some(i);
+            }
+        }
+    }
+
+    let mut counts = vec![0; items.len()];
+    let mut j = capacity;
+    while j > 0 {
+        match last_items[j] {
+            Some(i) => {
+                counts[i] += 1;
+                j -= items[i].weight;
+            },
+            None => j -= 1
+        }
+    }
+
+    knapsack_from_counts(items, &counts)
+}
+
+/// Knapsack with at most `counts[i]` copies of item `i`. Every item is split into groups of
+/// 1, 2, 4, ... copies, so it is a 0/1 knapsack with O(Σ log `counts[i]`) items.
+pub fn bounded_knapsack<'a, T: Number>(items: &'a [KnapsackItem<T>], counts: &[usize], capacity: usize) -> OptimalKnapsack<'a, T> {
+    assert_eq!(items.len(), counts.len(), "every item needs a count");
+
+    let mut groups = Vec::new();
+    let mut group_items = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let mut remaining = counts[i];
+        let mut size = 1;
+
+        while remaining > 0 {
+            let taken = size.min(remaining);
+            if let Some(weight) = item.weight.checked_mul(taken) {
+                groups.push(KnapsackItem { value: item.value * T::from_usize(taken), weight });
+                group_items.push((i, taken));
+            }
+
+            remaining -= taken;
+            size *= 2;
+        }
+    }
+
+    let mut quantities = vec![0; items.len()];
+    for group in knapsack_choose(&groups, capacity).2 {
+        let (i, taken) = group_items[group];
+        quantities[i] += taken;
+    }
+
+    knapsack_from_counts(items, &quantities)
+}
+
+/// Fractional knapsack: any fraction of an item may be taken. The greedy choice by value per
+/// unit of weight is optimal. Items of zero weight are always taken. With integer values the
+/// value of the partially taken item is rounded down.
+pub fn fractional_knapsack<T: Number>(items: &[KnapsackItem<T>], capacity: usize) -> OptimalKnapsack<'_, T> {
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by(|&a, &b| compare_ratios(&items[b], &items[a]));
+
+    let mut result = OptimalKnapsack { total_weight: 0, total_value: T::zero(), items: Vec::new(), quantities: Vec::new(), fractions: Vec::new() };
+    for i in order {
+        let remaining = capacity - result.total_weight;
+        if remaining == 0 && items[i].weight > 0 {
+            break;
+        }
+        if items[i].value <= T::zero() {
+            continue;
+        }
+
+        let taken = items[i].weight.min(remaining);
+        let (fraction, value) = if taken == items[i].weight {
+            (1.0, items[i].value)
+        }
+        else {
+            (taken as f64 / items[i].weight as f64, items[i].value.scale(taken, items[i].weight))
+        };
+        result.total_weight += taken;
+        result.total_value = result.total_value + value;
+        result.items.push(&items[i]);
+        result.quantities.push(1);
+        result.fractions.push(fraction);
+    }
+
+    result
+}
+
+/// 0/1 knapsack with two constraints: the weight and the volume (`volumes[i]` for item `i`) of
+/// the chosen items. O(n W V) time and space.
+pub fn knapsack_2d<'a, T: Number>(items: &'a [KnapsackItem<T>], volumes: &[usize], capacity: usize, volume_capacity: usize) -> OptimalKnapsack<'a, T> {
+    assert_eq!(items.len(), volumes.len(), "every item needs a volume");
+
+    let n = capacity + 1;
+    let cells = n * (volume_capacity + 1);
+    let mut best_values = vec![T::zero(); cells];
+    let mut chosen_items = vec![false; items.len() * cells];
+
+    for (i, item) in items.iter().enumerate() {
+        // Going down, so that every cell still holds the value without item i.
+        for v in (volumes[i]..=volume_capacity).rev() {
+            for w in (item.weight..=capacity).rev() {
+                let with_item = best_values[idx(w - item.weight, v - volumes[i], n)] + item.value;
+
+                if with_item > best_values[idx(w, v, n)] {
+                    best_values[idx(w, v, n)] = with_item;
+                    chosen_items[i * cells + idx(w, v, n)] = true;
+                }
+            }
+        }
+    }
+
+    let mut counts = vec![0; items.len()];
+    let (mut w, mut v) = (capacity, volume_capacity);
+    for i in (0..items.len()).rev() {
+        if chosen_items[i * cells + idx(w, v, n)] {
+            counts[i] = 1;
+            w -= items[i].weight;
+            v -= volumes[i];
+        }
+    }
+
+    knapsack_from_counts(items, &counts)
+}
+
+/// 0/1 knapsack indexed by value instead of weight: finds the lightest set of items for every
+/// total value. Values must be non-negative integers; the capacity may be huge.
+/// O(n V) time and space, where V is the sum of the values.
+pub fn knapsack_by_value<T: Number>(items: &[KnapsackItem<T>], capacity: usize) -> OptimalKnapsack<'_, T> {
+    let values: Vec<usize> = items.iter()
+        .map(|item| item.value.to_usize().expect("values must be non-negative integers"))
+        .collect();
+    let chosen = knapsack_choose_by_value(items, &values, capacity);
+
+    knapsack_from_counts(items, &chosen)
+}
+
+/// Fully polynomial-time approximation scheme for the 0/1 knapsack: values are scaled down so
+/// that the value-indexed dynamic programming takes O(n³ / ε) time. The value of the result is
+/// at least `(1 - epsilon)` times the optimum.
+pub fn knapsack_fptas<T: Number>(items: &[KnapsackItem<T>], capacity: usize, epsilon: f64) -> OptimalKnapsack<'_, T> {
+    assert!(epsilon > 0.0 && epsilon < 1.0, "epsilon must be between 0 and 1");
+
+    // Items that do not fit alone could make the scale much too coarse.
+    let largest = items.iter()
+        .filter(|item| item.weight <= capacity)
+        .map(|item| item.value.to_f64())
+        .fold(0.0, f64::max);
+    if largest <= 0.0 {
+        return knapsack_from_counts(items, &vec![0; items.len()]);
+    }
+
+    let scale = epsilon * largest / items.len() as f64;
+    let values: Vec<usize> = items.iter()
+        .map(|item| item.value.to_f64())
+        .zip(items)
+        .map(|(value, item)| if item.weight <= capacity && value > 0.0 { (value / scale).floor() as usize } else { 0 })
+        .collect();
+    let chosen = knapsack_choose_by_value(items, &values, capacity);
+
+    knapsack_from_counts(items, &chosen)
+}
+
+// 0/1 knapsack over integer values; returns 1 for chosen items and 0 for the rest.
+fn knapsack_choose_by_value<T>(items: &[KnapsackItem<T>], values: &[usize], capacity: usize) -> Vec<usize> {
+    let total: usize = values.iter().sum();
+    let n = total + 1;
+    // The lightest weight of items with exactly the given value, None if there are none.
+    let mut lightest: Vec<Option<usize>> = vec![None; n];
+    let mut chosen_items = vec![false; items.len() * n];
+    lightest[0] = Some(0);
+
+    for (i, item) in items.iter().enumerate() {
+        for v in (values[i]..=total).rev() {
+            let with_item = lightest[v - values[i]].and_then(|weight| weight.checked_add(item.weight));
+
+            if let Some(weight) = with_item.filter(|&weight| weight <= capacity) {
+                if lightest[v].is_none_or(|best| weight < best) {
+                    lightest[v] = Some(weight);
+                    chosen_items[idx(v, i, n)] = true;
+                }
+            }
+        }
+    }
+
+    let mut counts = vec![0; items.len()];
+    let mut v = (0..n).rev().find(|&v| lightest[v].is_some()).unwrap();
+    for i in (0..items.len()).rev() {
+        if chosen_items[idx(v, i, n)] {
+            counts[i] = 1;
+            v -= values[i];
+        }
+    }
+
+    counts
+}
+
+/// Exact 0/1 knapsack by branch and bound: a depth-first search over the items by decreasing
+/// value per weight, pruning branches whose fractional knapsack bound cannot beat the best
+/// solution found. Exponential in the worst case, but independent of the capacity.
+pub fn knapsack_branch_and_bound<T: Number>(items: &[KnapsackItem<T>], capacity: usize) -> OptimalKnapsack<'_, T> {
+    let mut order: Vec<usize> = (0..items.len())
+        .filter(|&i| items[i].weight <= capacity && items[i].value > T::zero())
+        .collect();
+    order.sort_by(|&a, &b| compare_ratios(&items[b], &items[a]));
+
+    struct Search<'a, T> {
+        items: &'a [KnapsackItem<T>],
+        order: Vec<usize>,
+        taken: Vec<bool>,
+        best_value: T,
+        best: Vec<bool>
+    }
+
+    impl<T: Number> Search<'_, T> {
+        // Value of the fractional knapsack over the items from position `next` on. Rounding
+        // integer values down keeps it an upper bound of the integral optimum.
+        fn bound(&self, next: usize, mut remaining: usize) -> T {
+            let mut bound = T::zero();
+            for &i in &self.order[next..] {
+                let item = &self.items[i];
+                if item.weight <= remaining {
+                    bound = bound + item.value;
+                    remaining -= item.weight;
+                }
+                else {
+                    return bound + item.value.scale(remaining, item.weight);
+                }
+            }
+
+            bound
+        }
+
+        fn search(&mut self, next: usize, remaining: usize, value: T) {
+            if value > self.best_value {
+                self.best_value = value;
+                self.best.clone_from(&self.taken);
+            }
+            if next == self.order.len() || value + self.bound(next, remaining) <= self.best_value {
+                return;
+            }
+
+            let item = &self.items[self.order[next]];
+            if item.weight <= remaining {
+                self.taken[next] = true;
+                self.search(next + 1, remaining - item.weight, value + item.value);
+                self.taken[next] = false;
+            }
+            self.search(next + 1, remaining, value);
+        }
+    }
+
+    let length = order.len();
+    let mut search = Search { items, order, taken: vec![false; length], best_value: T::zero(), best: vec![false; length] };
+    search.search(0, capacity, T::zero());
+
+    let mut counts = vec![0; items.len()];
+    for (position, &i) in search.order.iter().enumerate() {
+        if search.best[position] {
+            counts[i] = 1;
+        }
+    }
+
+    knapsack_from_counts(items, &counts)
+}
+
+// Compares the values per unit of weight v1 / w1 and v2 / w2 without dividing by zero weights.
+fn compare_ratios<T: Number>(a: &KnapsackItem<T>, b: &KnapsackItem<T>) -> Ordering {
+    a.value.cmp_scaled(b.weight, b.value, a.weight)
+}
+
+fn knapsack_from_counts<'a, T: Number>(items: &'a [KnapsackItem<T>], counts: &[usize]) -> OptimalKnapsack<'a, T> {
+    let mut result = OptimalKnapsack { total_weight: 0, total_value: T::zero(), items: Vec::new(), quantities: Vec::new(), fractions: Vec::new() };
+
+    for (item, &count) in items.iter().zip(counts) {
+        if count > 0 {
+            result.total_weight += item.weight * count;
+            result.total_value = result.total_value + item.value * T::from_usize(count);
+            result.items.push(item);
+            result.quantities.push(count);
+        }
+    }
+
+    result
+}
+
 #[derive(Debug, PartialEq)]
-pub struct KnapsackItem {
-    pub value: f64,
+pub struct KnapsackItem<T = f64> {
+    pub value: T,
     pub weight: usize
 }
 
-pub struct OptimalKnapsack<'a> {
+pub struct OptimalKnapsack<'a, T = f64> {
     pub total_weight: usize,
-    pub total_value: f64,
-    pub items: Vec<&'a KnapsackItem>
+    pub total_value: T,
+    pub items: Vec<&'a KnapsackItem<T>>,
+    /// How many copies of every chosen item are taken. Always 1 for the fractional knapsack.
+    pub quantities: Vec<usize>,
+    /// The part of every chosen item taken by the fractional knapsack, at most one of them
+    /// below 1. Empty for the other variants, which take whole items.
+    pub fractions: Vec<f64>
 }
 
+/// Longest common subsequence of two strings (the name is kept for compatibility; the result
+/// does not have to be contiguous). See `longest_common_subsequence` for generic sequences,
+/// and `longest_contiguous_common_substring` or `suffix_array::longest_common_substring`
+/// for a contiguous common substring.
+#[deprecated(note = "computes a subsequence; use `longest_common_subsequence`, or `longest_contiguous_common_substring` for a contiguous substring")]
 pub fn longest_common_substring(x: &String, y: &String) -> String {
     let x_chars: Vec<char> = x.chars().collect();
     let y_chars: Vec<char> = y.chars().collect();
-    
-    let mut lcs_table = vec![LcsData { length: 0, longest_substring: LcsBestSubstring::Both }; x_chars.len() * y_chars.len()];
 
-    fn get_lc_lengths(data: &Vec<LcsData>, i: usize, j: usize, n: usize) -> usize {
+    longest_common_subsequence(&x_chars, &y_chars).into_iter()
+        .map(|(i, _)| x_chars[i])
+        .collect()
+}
+
+/// Longest common subsequence of two sequences. Returns the alignment: pairs of indices
+/// `(i, j)` such that `x[i] == y[j]`, increasing in both coordinates.
+pub fn longest_common_subsequence<T: PartialEq>(x: &[T], y: &[T]) -> Vec<(usize, usize)> {
+    let mut lcs_table = vec![LcsData { length: 0, longest_substring: LcsBestSubstring::Both }; x.len() * y.len()];
+
+    fn get_lc_lengths(data: &[LcsData], i: usize, j: usize, n: usize) -> usize {
         if i == 0 || j == 0 {
             0
         }
@@ -187,16 +740,16 @@ pub fn longest_common_substring(x: &String, y: &String) -> String {
         }
     }
 
-    for i in 1..=x_chars.len() {
-        for j in 1..=y_chars.len() {
+    for i in 1..=x.len() {
+        for j in 1..=y.len() {
             let mut best = LcsData { length: 0, longest_substring: LcsBestSubstring::Both };
-            if x_chars[i - 1] == y_chars[j - 1] {
-                best.length = get_lc_lengths(&lcs_table, i - 1, j - 1, x_chars.len()) + 1;
+            if x[i - 1] == y[j - 1] {
+                best.length = get_lc_lengths(&lcs_table, i - 1, j - 1, x.len()) + 1;
                 best.longest_substring = LcsBestSubstring::Both;
             }
             else {
-                let best_i = get_lc_lengths(&lcs_table, i - 1, j, x_chars.len());
-                let best_j = get_lc_lengths(&lcs_table, i, j - 1, x_chars.len());
+                let best_i = get_lc_lengths(&lcs_table, i - 1, j, x.len());
+                let best_j = get_lc_lengths(&lcs_table, i, j - 1, x.len());
 
                 if best_i > best_j {
                     best.length = best_i;
@@ -208,7 +761,7 @@ pub fn longest_common_substring(x: &String, y: &String) -> String {
                 }
             }
 
-            lcs_table[idx(i - 1, j - 1, x_chars.len())] = best;
+            lcs_table[idx(i - 1, j - 1, x.len())] = best;
         }
     }
 
@@ -218,10 +771,10 @@ pub fn longest_common_substring(x: &String, y: &String) -> String {
     let mut j = y.len();
 
     while i > 0 && j > 0 {
-        let current = &lcs_table[idx(i - 1, j - 1, x_chars.len())];
+        let current = &lcs_table[idx(i - 1, j - 1, x.len())];
         match current.longest_substring {
             LcsBestSubstring::Both => {
-                result.push(x_chars[i - 1]);
+                result.push((i - 1, j - 1));
                 i -= 1;
                 j -= 1;
             },
@@ -235,62 +788,616 @@ pub fn longest_common_substring(x: &String, y: &String) -> String {
     }
 
     result.reverse();
-
-    result.into_iter().collect()
+    result
 }
 
-#[derive(Copy, Clone)]
-enum LcsBestSubstring {
-    Both,
-    X,
-    Y
-}
+/// Enumerates all distinct longest common subsequences, in lexicographic order.
+/// Note that there may be exponentially many of them.
+pub fn all_longest_common_subsequences<T: Ord + Clone>(x: &[T], y: &[T]) -> Vec<Vec<T>> {
+    let lengths = lcs_length_table(x, y);
+    let mut memo = HashMap::new();
 
-#[derive(Copy, Clone)]
-struct LcsData {
-    length: usize,
-    longest_substring: LcsBestSubstring
+    fn collect<T: Ord + Clone>(x: &[T], y: &[T], lengths: &[usize], i: usize, j: usize,
+        memo: &mut HashMap<(usize, usize), BTreeSet<Vec<T>>>) -> BTreeSet<Vec<T>> {
+        let n = x.len() + 1;
+        if lengths[idx(i, j, n)] == 0 {
+            return BTreeSet::from([Vec::new()]);
+        }
+        if let Some(result) = memo.get(&(i, j)) {
+            return result.clone();
+        }
+
+        let mut result = BTreeSet::new();
+        if x[i - 1] == y[j - 1] {
+            for mut subsequence in collect(x, y, lengths, i - 1, j - 1, memo) {
+                subsequence.push(x[i - 1].clone());
+                result.insert(subsequence);
+            }
+        }
+        else {
+            if lengths[idx(i - 1, j, n)] == lengths[idx(i, j, n)] {
+                result.extend(collect(x, y, lengths, i - 1, j, memo));
+            }
+            if lengths[idx(i, j - 1, n)] == lengths[idx(i, j, n)] {
+                result.extend(collect(x, y, lengths, i, j - 1, memo));
+            }
+        }
+
+        memo.insert((i, j), result.clone());
+        result
+    }
+
+    collect(x, y, &lengths, x.len(), y.len(), &mut memo).into_iter().collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// Lengths of LCS of all prefixes: entry idx(i, j, x.len() + 1) is for x[..i] and y[..j].
+fn lcs_length_table<T: PartialEq>(x: &[T], y: &[T]) -> Vec<usize> {
+    let n = x.len() + 1;
+    let mut lengths = vec![0; n * (y.len() + 1)];
 
-    #[test]
-    fn cut_rod_single_cut_test() {
-        let prices : Vec<f64> = vec![1.0, 5.0, 8.0, 10.0, 13.0, 17.0, 18.0, 22.0, 25.0, 30.0];
-        let (profit, cuts) = cut_rod(&prices);
+    for j in 1..=y.len() {
+        for i in 1..=x.len() {
+            lengths[idx(i, j, n)] = if x[i - 1] == y[j - 1] {
+                lengths[idx(i - 1, j - 1, n)] + 1
+            }
+            else {
+                lengths[idx(i - 1, j, n)].max(lengths[idx(i, j - 1, n)])
+            };
+        }
+    }
 
-        assert_eq!(30.0, profit);
-        assert_eq!(vec![10], cuts);
+    lengths
+}
+
+/// Hirschberg's algorithm: the same result as `longest_common_subsequence`, but in
+/// O(min(n, m)) additional space and O(nm) time.
+pub fn hirschberg_lcs<T: PartialEq>(x: &[T], y: &[T]) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+
+    // The rows are as long as the second sequence, so it should be the shorter one.
+    if y.len() > x.len() {
+        hirschberg_step(y, x, 0, 0, &mut result);
+        result.iter_mut().for_each(|pair| *pair = (pair.1, pair.0));
+    }
+    else {
+        hirschberg_step(x, y, 0, 0, &mut result);
     }
 
-    #[test]
-    fn cut_rod_two_cuts_test() {
-        let prices : Vec<f64> = vec![1.0, 5.0, 8.0, 10.0, 13.0, 17.0, 18.0];
-        let (profit, cuts) = cut_rod(&prices);
+    result
+}
 
-        assert_eq!(18.0, profit);
-        assert_eq!(vec![1, 6], cuts);
+fn hirschberg_step<T: PartialEq>(x: &[T], y: &[T], x_offset: usize, y_offset: usize, result: &mut Vec<(usize, usize)>) {
+    if x.is_empty() || y.is_empty() {
+        return;
     }
-    
-    #[test]
-    fn matrix_mul_test() {
-        let data = vec![
-            MatrixSize { rows: 30, cols: 35 },
-            MatrixSize { rows: 35, cols: 15 },
-            MatrixSize { rows: 15, cols: 5 },
-            MatrixSize { rows: 5, cols: 10 },
-            MatrixSize { rows: 10, cols: 20 },
-            MatrixSize { rows: 20, cols: 25 }
-        ];
 
-        let result = matrix_mul(&data);
-        
-        assert_eq!(15125, result.multiplications);
-        assert_eq!("((A0*(A1*A2))*((A3*A4)*A5))", result.to_string())
+    if x.len() == 1 {
+        if let Some(j) = y.iter().position(|value| *value == x[0]) {
+            result.push((x_offset, y_offset + j));
+        }
+        return;
+    }
+
+    // Split x in half and find where the optimal path crosses the middle row.
+    let middle = x.len() / 2;
+    let forward = lcs_last_row(x[..middle].iter(), y.iter(), y.len());
+    let backward = lcs_last_row(x[middle..].iter().rev(), y.iter().rev(), y.len());
+
+    let split = (0..=y.len())
+        .max_by_key(|&k| (forward[k] + backward[y.len() - k], std::cmp::Reverse(k)))
+        .unwrap();
+
+    hirschberg_step(&x[..middle], &y[..split], x_offset, y_offset, result);
+    hirschberg_step(&x[middle..], &y[split..], x_offset + middle, y_offset + split, result);
+}
+
+// LCS lengths of the whole x and every prefix of y, computed row by row in linear space.
+fn lcs_last_row<'a, T: PartialEq + 'a, X, Y>(x: X, y: Y, y_length: usize) -> Vec<usize>
+    where X: Iterator<Item = &'a T>, Y: Iterator<Item = &'a T> + Clone {
+    let mut previous = vec![0; y_length + 1];
+    let mut current = vec![0; y_length + 1];
+
+    for x_value in x {
+        for (j, y_value) in y.clone().enumerate() {
+            current[j + 1] = if x_value == y_value {
+                previous[j] + 1
+            }
+            else {
+                previous[j + 1].max(current[j])
+            };
+        }
+
+        swap(&mut previous, &mut current);
+    }
+
+    previous
+}
+
+/// A contiguous substring common to two sequences, with its offsets in both of them.
+#[derive(Debug, PartialEq)]
+pub struct ContiguousCommonSubstring<'a, T> {
+    pub substring: &'a [T],
+    pub x_offset: usize,
+    pub y_offset: usize
+}
+
+/// Longest contiguous substring common to both sequences, found with the O(nm) dynamic
+/// programming over lengths of common suffixes of all pairs of prefixes. Only two rows of the
+/// table are kept. `suffix_array::longest_common_substring` solves the same problem with
+/// a suffix array, and `suffix_array::longest_common_substring_of_all` for more sequences.
+pub fn longest_contiguous_common_substring<'a, T: PartialEq>(x: &'a [T], y: &[T]) -> Option<ContiguousCommonSubstring<'a, T>> {
+    let mut previous = vec![0; y.len() + 1];
+    let mut current = vec![0; y.len() + 1];
+    // Length and the ends in x and y of the best substring so far.
+    let mut best = (0, 0, 0);
+
+    for i in 1..=x.len() {
+        for j in 1..=y.len() {
+            current[j] = if x[i - 1] == y[j - 1] { previous[j - 1] + 1 } else { 0 };
+
+            if current[j] > best.0 {
+                best = (current[j], i, j);
+            }
+        }
+
+        swap(&mut previous, &mut current);
+    }
+
+    let (length, x_end, y_end) = best;
+    if length == 0 {
+        return None;
+    }
+
+    Some(ContiguousCommonSubstring { substring: &x[x_end - length..x_end], x_offset: x_end - length, y_offset: y_end - length })
+}
+
+/// Operations of the edit distance problem (CLRS problem 15-5), transforming `x` into `y`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EditOperation {
+    /// Copies the current character of `x`; only possible if it equals the current one of `y`.
+    Copy,
+    /// Replaces the current character of `x` by the current one of `y`.
+    Replace,
+    Delete,
+    Insert,
+    /// Exchanges the next two characters of `x`.
+    Twiddle,
+    /// Deletes the rest of `x`; only possible as the last operation.
+    Kill
+}
+
+/// Costs of the edit operations. Twiddle and kill are optional; `None` disables them.
+#[derive(Debug, Clone)]
+pub struct EditCosts {
+    pub copy: usize,
+    pub replace: usize,
+    pub delete: usize,
+    pub insert: usize,
+    pub twiddle: Option<usize>,
+    pub kill: Option<usize>
+}
+
+impl EditCosts {
+    /// Unit costs of the Levenshtein distance: free copy, no twiddle or kill.
+    pub fn levenshtein() -> EditCosts {
+        EditCosts { copy: 0, replace: 1, delete: 1, insert: 1, twiddle: None, kill: None }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EditDistance {
+    pub cost: usize,
+    pub operations: Vec<EditOperation>
+}
+
+/// Cheapest sequence of operations transforming `x` into `y`, with the costs given. O(nm) time
+/// and space.
+pub fn edit_distance<T: PartialEq>(x: &[T], y: &[T], costs: &EditCosts) -> EditDistance {
+    let n = x.len() + 1;
+    let mut cost = vec![usize::MAX; n * (y.len() + 1)];
+    let mut operations = vec![EditOperation::Copy; n * (y.len() + 1)];
+    cost[idx(0, 0, n)] = 0;
+
+    for j in 0..=y.len() {
+        for i in 0..=x.len() {
+            let mut best = cost[idx(i, j, n)];
+            let mut best_operation = operations[idx(i, j, n)];
+            let mut consider = |candidate: usize, operation: EditOperation| {
+                if candidate < best {
+                    best = candidate;
+                    best_operation = operation;
+                }
+            };
+
+            if i > 0 && j > 0 {
+                if x[i - 1] == y[j - 1] {
+                    consider(cost[idx(i - 1, j - 1, n)] + costs.copy, EditOperation::Copy);
+                }
+                consider(cost[idx(i - 1, j - 1, n)] + costs.replace, EditOperation::Replace);
+            }
+            if i > 0 {
+                consider(cost[idx(i - 1, j, n)] + costs.delete, EditOperation::Delete);
+            }
+            if j > 0 {
+                consider(cost[idx(i, j - 1, n)] + costs.insert, EditOperation::Insert);
+            }
+            if let Some(twiddle) = costs.twiddle {
+                if i > 1 && j > 1 && x[i - 1] == y[j - 2] && x[i - 2] == y[j - 1] {
+                    consider(cost[idx(i - 2, j - 2, n)] + twiddle, EditOperation::Twiddle);
+                }
+            }
+
+            cost[idx(i, j, n)] = best;
+            operations[idx(i, j, n)] = best_operation;
+        }
+    }
+
+    // Kill may end the sequence at any prefix of x once y is complete.
+    let mut end = x.len();
+    let mut total = cost[idx(x.len(), y.len(), n)];
+    if let Some(kill) = costs.kill {
+        for i in 0..x.len() {
+            if cost[idx(i, y.len(), n)] + kill < total {
+                total = cost[idx(i, y.len(), n)] + kill;
+                end = i;
+            }
+        }
+    }
+
+    // Walking back
+    let mut result = Vec::new();
+    if end < x.len() {
+        result.push(EditOperation::Kill);
+    }
+
+    let (mut i, mut j) = (end, y.len());
+    while i > 0 || j > 0 {
+        let operation = operations[idx(i, j, n)];
+        result.push(operation);
+
+        match operation {
+            EditOperation::Copy | EditOperation::Replace => {
+                i -= 1;
+                j -= 1;
+            },
+            EditOperation::Delete => i -= 1,
+            EditOperation::Insert => j -= 1,
+            EditOperation::Twiddle => {
+                i -= 2;
+                j -= 2;
+            },
+            EditOperation::Kill => unreachable!()
+        }
+    }
+
+    result.reverse();
+    EditDistance { cost: total, operations: result }
+}
+
+/// Levenshtein distance: minimal number of insertions, deletions and substitutions. Keeps two
+/// rows of the table.
+pub fn levenshtein<T: PartialEq>(x: &[T], y: &[T]) -> usize {
+    let mut previous: Vec<usize> = (0..=y.len()).collect();
+    let mut current = vec![0; y.len() + 1];
+
+    for i in 1..=x.len() {
+        current[0] = i;
+        for j in 1..=y.len() {
+            let substitution = previous[j - 1] + if x[i - 1] == y[j - 1] { 0 } else { 1 };
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1);
+        }
+
+        swap(&mut previous, &mut current);
+    }
+
+    previous[y.len()]
+}
+
+/// Levenshtein distance limited to `k`: only the band of the table with `|i - j| <= k` is
+/// computed, and the computation stops as soon as a whole row exceeds `k`. Returns `None` if the
+/// distance is greater than `k`. O(k min(n, m)) time.
+pub fn levenshtein_bounded<T: PartialEq>(x: &[T], y: &[T], k: usize) -> Option<usize> {
+    if x.len().abs_diff(y.len()) > k {
+        return None;
+    }
+
+    // Cells outside of the band hold k + 1, which stands for "more than k".
+    let limit = k + 1;
+    let mut previous: Vec<usize> = (0..=y.len()).map(|j| j.min(limit)).collect();
+    let mut current = vec![limit; y.len() + 1];
+
+    for i in 1..=x.len() {
+        let from = i.saturating_sub(k).max(1);
+        let to = (i + k).min(y.len());
+        let mut row_minimum = limit;
+
+        current[from - 1] = if from == 1 { i.min(limit) } else { limit };
+        for j in from..=to {
+            let substitution = previous[j - 1] + if x[i - 1] == y[j - 1] { 0 } else { 1 };
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1).min(limit);
+            row_minimum = row_minimum.min(current[j]);
+        }
+        if to < y.len() {
+            current[to + 1] = limit;
+        }
+
+        if row_minimum.min(current[from - 1]) > k {
+            return None;
+        }
+
+        swap(&mut previous, &mut current);
+    }
+
+    Some(previous[y.len()]).filter(|&distance| distance <= k)
+}
+
+/// Optimal string alignment distance: Levenshtein with transpositions of adjacent elements,
+/// where no substring is edited more than once (so it is not a metric).
+pub fn optimal_string_alignment<T: PartialEq>(x: &[T], y: &[T]) -> usize {
+    let n = x.len() + 1;
+    let mut distance = vec![0; n * (y.len() + 1)];
+
+    for i in 0..=x.len() {
+        distance[idx(i, 0, n)] = i;
+    }
+    for j in 0..=y.len() {
+        distance[idx(0, j, n)] = j;
+    }
+
+    for j in 1..=y.len() {
+        for i in 1..=x.len() {
+            let substitution = distance[idx(i - 1, j - 1, n)] + if x[i - 1] == y[j - 1] { 0 } else { 1 };
+            let mut best = substitution.min(distance[idx(i - 1, j, n)] + 1).min(distance[idx(i, j - 1, n)] + 1);
+
+            if i > 1 && j > 1 && x[i - 1] == y[j - 2] && x[i - 2] == y[j - 1] {
+                best = best.min(distance[idx(i - 2, j - 2, n)] + 1);
+            }
+
+            distance[idx(i, j, n)] = best;
+        }
+    }
+
+    distance[idx(x.len(), y.len(), n)]
+}
+
+/// Damerau-Levenshtein distance with unrestricted adjacent transpositions (Lowrance-Wagner),
+/// so substrings may be edited again after a transposition.
+pub fn damerau_levenshtein<T: Eq + Hash>(x: &[T], y: &[T]) -> usize {
+    // The table is shifted by one row and column holding an "infinite" distance.
+    let n = x.len() + 2;
+    let infinity = x.len() + y.len();
+    let mut distance = vec![0; n * (y.len() + 2)];
+    let mut last_row: HashMap<&T, usize> = HashMap::new();
+
+    distance[idx(0, 0, n)] = infinity;
+    for i in 0..=x.len() {
+        distance[idx(i + 1, 0, n)] = infinity;
+        distance[idx(i + 1, 1, n)] = i;
+    }
+    for j in 0..=y.len() {
+        distance[idx(0, j + 1, n)] = infinity;
+        distance[idx(1, j + 1, n)] = j;
+    }
+
+    for i in 1..=x.len() {
+        let mut last_column = 0;
+
+        for j in 1..=y.len() {
+            let k = last_row.get(&y[j - 1]).copied().unwrap_or(0);
+            let l = last_column;
+            let cost = if x[i - 1] == y[j - 1] {
+                last_column = j;
+                0
+            }
+            else {
+                1
+            };
+
+            distance[idx(i + 1, j + 1, n)] = (distance[idx(i, j, n)] + cost)
+                .min(distance[idx(i + 1, j, n)] + 1)
+                .min(distance[idx(i, j + 1, n)] + 1)
+                .min(distance[idx(k, l, n)] + (i - k - 1) + 1 + (j - l - 1));
+        }
+
+        last_row.insert(&x[i - 1], i);
+    }
+
+    distance[idx(x.len() + 1, y.len() + 1, n)]
+}
+
+#[derive(Copy, Clone)]
+enum LcsBestSubstring {
+    Both,
+    X,
+    Y
+}
+
+#[derive(Copy, Clone)]
+struct LcsData {
+    length: usize,
+    longest_substring: LcsBestSubstring
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::number::Ratio;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn cut_rod_single_cut_test() {
+        let prices : Vec<f64> = vec![1.0, 5.0, 8.0, 10.0, 13.0, 17.0, 18.0, 22.0, 25.0, 30.0];
+        let (profit, cuts) = cut_rod(&prices);
+
+        assert_eq!(30.0, profit);
+        assert_eq!(vec![10], cuts);
+    }
+
+    #[test]
+    fn cut_rod_two_cuts_test() {
+        let prices : Vec<f64> = vec![1.0, 5.0, 8.0, 10.0, 13.0, 17.0, 18.0];
+        let (profit, cuts) = cut_rod(&prices);
+
+        assert_eq!(18.0, profit);
+        assert_eq!(vec![1, 6], cuts);
+    }
+
+    #[test]
+    fn cut_rod_integer_and_ratio_test() {
+        let cents: Vec<u64> = vec![10, 20, 30];
+        assert_eq!((30, vec![1, 1, 1]), cut_rod(&cents));
+
+        let prices = vec![Ratio::new(1, 10), Ratio::new(1, 5), Ratio::new(3, 10)];
+        assert_eq!((Ratio::new(3, 10), vec![1, 1, 1]), cut_rod(&prices));
+        assert_eq!((0, vec![]), cut_rod::<i32>(&[]));
+    }
+
+    #[test]
+    fn cut_rod_with_cost_test() {
+        assert_eq!((10, vec![4]), cut_rod_with_cost(&[1, 5, 8, 10], 1));
+        assert_eq!((10, vec![1, 3]), cut_rod_with_cost(&[3, 5, 8, 9], 1));
+        assert_eq!((12, vec![1, 1, 1, 1]), cut_rod_with_cost(&[3, 5, 8, 9], 0));
+        assert_eq!((9, vec![4]), cut_rod_with_cost(&[3, 5, 8, 9], 5u32));
+    }
+
+    #[test]
+    fn cut_rods_test() {
+        let prices = vec![1, 5];
+        let expected = vec![(0, vec![]), (11, vec![1, 2, 2]), (5, vec![2]), (10, vec![2, 2])];
+        assert_eq!(expected, cut_rods(&prices, &[0, 5, 2, 4]));
+        assert_eq!(vec![(0, vec![])], cut_rods::<i32>(&[], &[0]));
+    }
+
+    #[test]
+    fn cut_rod_memoized_long_rod_test() {
+        // Deep enough to overflow a small stack if every length took a call frame.
+        let prices: Vec<i64> = (0..10_000).map(|j| j * 3 + (j * 7919) % 101).collect();
+        let expected = cut_rod(&prices);
+        let result = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || cut_rod_memoized(&prices))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(expected, result);
+        assert_eq!((0, vec![]), cut_rod_memoized::<i32>(&[]));
+    }
+
+    #[test]
+    fn cut_rod_bounded_test() {
+        let prices = vec![1.0, 5.0, 8.0, 10.0, 13.0, 17.0, 18.0];
+        assert_eq!((18.0, vec![1, 6]), cut_rod_bounded(&prices, &[1, 0, 0, 0, 0, 1, 0]));
+        assert_eq!((13.0, vec![2, 3]), cut_rod_bounded(&prices, &[0, 1, 1, 0, 0, 0, 0]));
+        assert_eq!((0.0, vec![]), cut_rod_bounded(&prices, &[0; 7]));
+    }
+
+    // Best profit over all ways of cutting a rod into at most `limits` pieces of every length.
+    fn brute_force_cut_rod(prices: &[i64], length: usize, cut_cost: i64, limits: &mut [usize], scrap: bool) -> Option<i64> {
+        let mut best = if length == 0 || scrap { Some(0) } else { None };
+        for j in 0..length.min(prices.len()) {
+            if limits[j] > 0 {
+                limits[j] -= 1;
+                if let Some(rest) = brute_force_cut_rod(prices, length - j - 1, cut_cost, limits, scrap) {
+                    let cost = if j + 1 < length { cut_cost } else { 0 };
+                    best = Some(best.map_or(prices[j] + rest - cost, |best| best.max(prices[j] + rest - cost)));
+                }
+                limits[j] += 1;
+            }
+        }
+
+        best
+    }
+
+    #[test]
+    fn cut_rod_variants_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(49);
+
+        for _ in 0..300 {
+            let prices: Vec<i64> = (0..rng.gen_range(1..8)).map(|_| rng.gen_range(0..20)).collect();
+            let n = prices.len();
+            let cut_cost = rng.gen_range(0..5);
+            let limits: Vec<usize> = (0..n).map(|_| rng.gen_range(0..3)).collect();
+
+            let expected = cut_rod(&prices);
+            assert_eq!(expected, cut_rod_memoized(&prices));
+            let lengths: Vec<usize> = (0..=n).collect();
+            for (length, result) in cut_rods(&prices, &lengths).into_iter().enumerate() {
+                assert_eq!(cut_rod(&prices[..length]), result);
+            }
+
+            let (profit, cuts) = cut_rod_with_cost(&prices, cut_cost);
+            assert_eq!(brute_force_cut_rod(&prices, n, cut_cost, &mut vec![n; n], false), Some(profit));
+            assert_eq!(n, cuts.iter().sum());
+            assert_eq!(profit, cuts.iter().map(|&piece| prices[piece - 1]).sum::<i64>() - cut_cost * (cuts.len() as i64 - 1));
+
+            let (profit, cuts) = cut_rod_bounded(&prices, &limits);
+            assert_eq!(brute_force_cut_rod(&prices, n, 0, &mut limits.clone(), true), Some(profit));
+            assert!(cuts.iter().sum::<usize>() <= n);
+            assert_eq!(profit, cuts.iter().map(|&piece| prices[piece - 1]).sum());
+            for (j, &limit) in limits.iter().enumerate() {
+                assert!(cuts.iter().filter(|&&piece| piece == j + 1).count() <= limit);
+            }
+        }
     }
     
+    #[test]
+    fn matrix_mul_test() {
+        let data = vec![
+            MatrixSize { rows: 30, cols: 35 },
+            MatrixSize { rows: 35, cols: 15 },
+            MatrixSize { rows: 15, cols: 5 },
+            MatrixSize { rows: 5, cols: 10 },
+            MatrixSize { rows: 10, cols: 20 },
+            MatrixSize { rows: 20, cols: 25 }
+        ];
+
+        let result = matrix_mul(&data).unwrap();
+        
+        assert_eq!(15125, result.multiplications);
+        assert_eq!("((A0*(A1*A2))*((A3*A4)*A5))", result.to_string());
+
+        assert_eq!(Some(2), result.split(0, 5));
+        assert_eq!(Some(0), result.split(0, 2));
+        assert_eq!(Some(4), result.split(3, 5));
+        assert_eq!(None, result.split(0, 4));
+
+        // The deprecated table also has the splits of products outside of the order.
+        #[allow(deprecated)]
+        let order = &result.order;
+        assert_eq!(2, order[idx(0, 5, 6)]);
+        assert_eq!(0, order[idx(0, 2, 6)]);
+        assert_eq!(1, order[idx(1, 2, 6)]);
+        assert_eq!(4, order[idx(3, 5, 6)]);
+        assert_eq!(3, order[idx(3, 4, 6)]);
+        assert_eq!(2, order[idx(0, 4, 6)]);
+    }
+    
+    #[test]
+    fn matrix_mul_errors_test() {
+        let empty = matrix_mul(&[]).unwrap();
+        assert_eq!(0, empty.multiplications);
+        assert_eq!("", empty.to_string());
+        assert_eq!("A0", matrix_mul(&[MatrixSize { rows: 2, cols: 3 }]).unwrap().to_string());
+
+        let mismatched = [MatrixSize { rows: 2, cols: 3 }, MatrixSize { rows: 4, cols: 5 }];
+        assert_eq!(Some(MatrixChainError::DimensionMismatch(0)), matrix_mul(&mismatched).err());
+
+        let huge = [MatrixSize { rows: usize::MAX, cols: 2 }, MatrixSize { rows: 2, cols: usize::MAX }];
+        assert_eq!(Some(MatrixChainError::Overflow), matrix_mul(&huge).err());
+        let cube = [MatrixSize { rows: 1 << 30, cols: 1 << 30 }; 3];
+        assert_eq!(Some(MatrixChainError::Overflow), matrix_mul(&cube).err());
+
+        // The overflowing product of the first two matrices is not part of the optimal order.
+        let avoidable = [
+            MatrixSize { rows: 1 << 40, cols: 1 },
+            MatrixSize { rows: 1, cols: 1 << 40 },
+            MatrixSize { rows: 1 << 40, cols: 1 }
+        ];
+        assert_eq!(1 << 41, matrix_mul(&avoidable).unwrap().multiplications);
+    }
+
     #[test]
     fn knapsack_1_test() {
         let items = vec![
@@ -331,6 +1438,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn longest_common_substring_1_test() {
         let x = String::from("abcbdab");
         let y = String::from("bdcaba");
@@ -338,4 +1446,485 @@ mod tests {
         let result = longest_common_substring(&x, &y);
         assert_eq!(String::from("bdab"), result);
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[allow(deprecated)]
+    fn longest_common_substring_non_ascii_test() {
+        let x = String::from("żółtko");
+        let y = String::from("żółw i kot");
+
+        let result = longest_common_substring(&x, &y);
+        assert_eq!(String::from("żółko"), result);
+    }
+
+    #[test]
+    fn longest_common_subsequence_test() {
+        let x = vec![1, 2, 3, 2, 4, 1, 2];
+        let y = vec![2, 4, 3, 1, 2, 1];
+
+        let result = longest_common_subsequence(&x, &y);
+        assert_eq!(vec![(3, 0), (4, 1), (5, 3), (6, 4)], result);
+        assert!(longest_common_subsequence::<i32>(&[], &y).is_empty());
+    }
+
+    #[test]
+    fn all_longest_common_subsequences_test() {
+        let x: Vec<char> = "abcbdab".chars().collect();
+        let y: Vec<char> = "bdcaba".chars().collect();
+
+        let result: Vec<String> = all_longest_common_subsequences(&x, &y).into_iter()
+            .map(|subsequence| subsequence.into_iter().collect())
+            .collect();
+        assert_eq!(vec!["bcab", "bcba", "bdab"], result);
+        assert_eq!(vec![Vec::<char>::new()], all_longest_common_subsequences(&x, &['x']));
+    }
+
+    #[test]
+    fn hirschberg_lcs_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(39);
+
+        for _ in 0..200 {
+            let x: Vec<u8> = (0..rng.gen_range(0..40)).map(|_| rng.gen_range(b'a'..b'e')).collect();
+            let y: Vec<u8> = (0..rng.gen_range(0..80)).map(|_| rng.gen_range(b'a'..b'e')).collect();
+
+            let expected = longest_common_subsequence(&x, &y);
+            for (x, y) in [(&x, &y), (&y, &x)] {
+                let result = hirschberg_lcs(x, y);
+
+                assert_eq!(expected.len(), result.len());
+                assert!(result.iter().all(|&(i, j)| x[i] == y[j]));
+                assert!(result.windows(2).all(|pair| pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1));
+            }
+        }
+    }
+
+    #[test]
+    fn longest_contiguous_common_substring_test() {
+        let x: Vec<char> = "abcbdab".chars().collect();
+        let y: Vec<char> = "bdcaba".chars().collect();
+        let result = longest_contiguous_common_substring(&x, &y).unwrap();
+        assert_eq!(ContiguousCommonSubstring { substring: &['a', 'b'][..], x_offset: 0, y_offset: 3 }, result);
+
+        let x: Vec<char> = "zażółć gęślą".chars().collect();
+        let y: Vec<char> = "gęśla jaźń żółć".chars().collect();
+        let result = longest_contiguous_common_substring(&x, &y).unwrap();
+        assert_eq!(&['ż', 'ó', 'ł', 'ć'], result.substring);
+        assert_eq!((2, 11), (result.x_offset, result.y_offset));
+
+        assert_eq!(None, longest_contiguous_common_substring(b"abc", b"xyz"));
+    }
+
+    fn replay_edits<T: PartialEq + Clone>(x: &[T], y: &[T], costs: &EditCosts, result: &EditDistance) {
+        let mut transformed = Vec::new();
+        let (mut i, mut j, mut total) = (0, 0, 0);
+
+        for operation in &result.operations {
+            match operation {
+                EditOperation::Copy => {
+                    assert!(x[i] == y[j]);
+                    transformed.push(x[i].clone());
+                    total += costs.copy;
+                    i += 1;
+                    j += 1;
+                },
+                EditOperation::Replace => {
+                    transformed.push(y[j].clone());
+                    total += costs.replace;
+                    i += 1;
+                    j += 1;
+                },
+                EditOperation::Delete => {
+                    total += costs.delete;
+                    i += 1;
+                },
+                EditOperation::Insert => {
+                    transformed.push(y[j].clone());
+                    total += costs.insert;
+                    j += 1;
+                },
+                EditOperation::Twiddle => {
+                    transformed.push(x[i + 1].clone());
+                    transformed.push(x[i].clone());
+                    total += costs.twiddle.unwrap();
+                    i += 2;
+                    j += 2;
+                },
+                EditOperation::Kill => {
+                    total += costs.kill.unwrap();
+                    i = x.len();
+                }
+            }
+        }
+
+        assert_eq!(x.len(), i);
+        assert!(transformed == y);
+        assert_eq!(result.cost, total);
+    }
+
+    #[test]
+    fn edit_distance_test() {
+        let x: Vec<char> = "abcdef".chars().collect();
+        let y: Vec<char> = "bad".chars().collect();
+        let costs = EditCosts { copy: 0, replace: 2, delete: 2, insert: 2, twiddle: Some(1), kill: Some(1) };
+
+        let result = edit_distance(&x, &y, &costs);
+        assert_eq!(4, result.cost);
+        assert_eq!(Some(&EditOperation::Twiddle), result.operations.first());
+        assert_eq!(Some(&EditOperation::Kill), result.operations.last());
+        replay_edits(&x, &y, &costs, &result);
+
+        let x: Vec<char> = "kitten".chars().collect();
+        let y: Vec<char> = "sitting".chars().collect();
+        let result = edit_distance(&x, &y, &EditCosts::levenshtein());
+        assert_eq!(3, result.cost);
+        assert_eq!(vec![
+            EditOperation::Replace, EditOperation::Copy, EditOperation::Copy, EditOperation::Copy,
+            EditOperation::Replace, EditOperation::Copy, EditOperation::Insert
+        ], result.operations);
+    }
+
+    #[test]
+    fn levenshtein_family_test() {
+        assert_eq!(3, levenshtein(b"kitten", b"sitting"));
+        assert_eq!(0, levenshtein::<u8>(b"", b""));
+        assert_eq!(3, optimal_string_alignment(b"ca", b"abc"));
+        assert_eq!(2, damerau_levenshtein(b"ca", b"abc"));
+        assert_eq!(1, damerau_levenshtein(b"ab", b"ba"));
+        assert_eq!(Some(3), levenshtein_bounded(b"kitten", b"sitting", 3));
+        assert_eq!(None, levenshtein_bounded(b"kitten", b"sitting", 2));
+        assert_eq!(None, levenshtein_bounded(b"a", b"abcd", 2));
+    }
+
+    #[test]
+    fn edit_distance_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for _ in 0..300 {
+            let x: Vec<u8> = (0..rng.gen_range(0..12)).map(|_| rng.gen_range(b'a'..b'e')).collect();
+            let y: Vec<u8> = (0..rng.gen_range(0..12)).map(|_| rng.gen_range(b'a'..b'e')).collect();
+
+            let distance = levenshtein(&x, &y);
+            let levenshtein_costs = EditCosts::levenshtein();
+            let result = edit_distance(&x, &y, &levenshtein_costs);
+            assert_eq!(distance, result.cost);
+            replay_edits(&x, &y, &levenshtein_costs, &result);
+
+            let osa_costs = EditCosts { twiddle: Some(1), ..EditCosts::levenshtein() };
+            let result = edit_distance(&x, &y, &osa_costs);
+            assert_eq!(optimal_string_alignment(&x, &y), result.cost);
+            replay_edits(&x, &y, &osa_costs, &result);
+
+            let costs = EditCosts {
+                copy: rng.gen_range(0..3), replace: rng.gen_range(1..5), delete: rng.gen_range(1..5),
+                insert: rng.gen_range(1..5), twiddle: Some(rng.gen_range(1..5)), kill: Some(rng.gen_range(0..5))
+            };
+            replay_edits(&x, &y, &costs, &edit_distance(&x, &y, &costs));
+
+            let damerau = damerau_levenshtein(&x, &y);
+            assert!(damerau <= result.cost && result.cost <= distance);
+
+            for k in 0..6 {
+                assert_eq!(Some(distance).filter(|&distance| distance <= k), levenshtein_bounded(&x, &y, k));
+            }
+        }
+    }
+
+    #[test]
+    fn execute_test() {
+        let mut rng = StdRng::seed_from_u64(44);
+        let dimensions: Vec<usize> = (0..7).map(|_| rng.gen_range(1..6)).collect();
+        let matrices: Vec<Matrix<i64>> = dimensions.windows(2)
+            .map(|pair| Matrix::new(pair[0], pair[1], (0..pair[0] * pair[1]).map(|_| rng.gen_range(-5..5)).collect()))
+            .collect();
+        let sizes: Vec<MatrixSize> = matrices.iter().map(|matrix| matrix.size()).collect();
+
+        let order = matrix_mul(&sizes).unwrap();
+        let expected = matrices[1..].iter().fold(matrices[0].clone(), |product, matrix| product.multiply(matrix));
+        assert_eq!(Ok(expected), execute(&order, &matrices));
+
+        assert_eq!(Err(MatrixChainError::CountMismatch { expected: 6, actual: 5 }), execute(&order, &matrices[1..]));
+
+        let mut wrong = matrices.clone();
+        wrong[3] = Matrix::zeros(dimensions[3] + 1, dimensions[4]);
+        assert_eq!(Err(MatrixChainError::DimensionMismatch(2)), execute(&order, &wrong));
+
+        assert_eq!(Ok(()), validate_chain(&sizes));
+        assert_eq!(Err(MatrixChainError::DimensionMismatch(0)), validate_chain(&[MatrixSize { rows: 1, cols: 2 }, MatrixSize { rows: 3, cols: 1 }]));
+    }
+
+    #[test]
+    fn knapsack_nothing_fits_test() {
+        let items = vec![KnapsackItem { value: 1.0, weight: 5 }];
+        let result = knapsack(&items, 3);
+
+        assert!(result.items.is_empty());
+        assert_eq!(0.0, result.total_value);
+    }
+
+    #[test]
+    fn unbounded_knapsack_test() {
+        let items = vec![
+            KnapsackItem { value: 10.0, weight: 5 },
+            KnapsackItem { value: 40.0, weight: 4 },
+            KnapsackItem { value: 30.0, weight: 6 },
+            KnapsackItem { value: 50.0, weight: 3 }
+        ];
+
+        let result = unbounded_knapsack(&items, 8);
+        assert_eq!(100.0, result.total_value);
+        assert_eq!(vec![&items[3]], result.items);
+        assert_eq!(vec![2], result.quantities);
+        assert!(result.fractions.is_empty());
+        assert_eq!(6, result.total_weight);
+    }
+
+    fn brute_force_knapsack(items: &[KnapsackItem], counts: &[usize], volumes: &[usize], capacity: usize, volume_capacity: usize) -> f64 {
+        fn search(items: &[KnapsackItem], counts: &[usize], volumes: &[usize], i: usize, capacity: usize, volume_capacity: usize) -> f64 {
+            if i == items.len() {
+                return 0.0;
+            }
+
+            let mut best: f64 = 0.0;
+            for count in 0..=counts[i] {
+                if items[i].weight * count > capacity || volumes[i] * count > volume_capacity {
+                    break;
+                }
+                best = best.max(items[i].value * count as f64 + search(items, counts, volumes, i + 1,
+                    capacity - items[i].weight * count, volume_capacity - volumes[i] * count));
+            }
+
+            best
+        }
+
+        search(items, counts, volumes, 0, capacity, volume_capacity)
+    }
+
+    fn random_items(rng: &mut StdRng, count: usize) -> Vec<KnapsackItem> {
+        (0..count).map(|_| KnapsackItem { value: rng.gen_range(1..30) as f64, weight: rng.gen_range(1..8) }).collect()
+    }
+
+    #[test]
+    fn bounded_knapsack_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(46);
+
+        for _ in 0..200 {
+            let count = rng.gen_range(0..6);
+            let items = random_items(&mut rng, count);
+            let counts: Vec<usize> = items.iter().map(|_| rng.gen_range(0..6)).collect();
+            let capacity = rng.gen_range(0..30);
+
+            let result = bounded_knapsack(&items, &counts, capacity);
+            assert_eq!(brute_force_knapsack(&items, &counts, &vec![0; items.len()], capacity, 0), result.total_value);
+            assert!(result.total_weight <= capacity);
+
+            let unbounded = unbounded_knapsack(&items, capacity);
+            let unlimited: Vec<usize> = items.iter().map(|item| capacity / item.weight).collect();
+            assert_eq!(bounded_knapsack(&items, &unlimited, capacity).total_value, unbounded.total_value);
+        }
+    }
+
+    #[test]
+    fn fractional_knapsack_test() {
+        let items = vec![
+            KnapsackItem { value: 60.0, weight: 10 },
+            KnapsackItem { value: 100.0, weight: 20 },
+            KnapsackItem { value: 120.0, weight: 30 }
+        ];
+
+        let result = fractional_knapsack(&items, 50);
+        assert_eq!(240.0, result.total_value);
+        assert_eq!(50, result.total_weight);
+        assert_eq!(vec![1, 1, 1], result.quantities);
+        assert_eq!(vec![1.0, 1.0, 2.0 / 3.0], result.fractions);
+    }
+
+    #[test]
+    fn fractional_knapsack_optimality_test() {
+        // With integer weights and capacity, the greedy solution only takes whole units of
+        // weight, so it is the best 0/1 choice of unit slices of all items: no solution is better.
+        let mut rng = StdRng::seed_from_u64(146);
+
+        for _ in 0..200 {
+            let count = rng.gen_range(0..8);
+            let items = random_items(&mut rng, count);
+            let capacity = rng.gen_range(0..40);
+
+            let slices: Vec<KnapsackItem> = items.iter()
+                .map(|item| KnapsackItem { value: item.value / item.weight as f64, weight: 1 })
+                .collect();
+            let counts: Vec<usize> = items.iter().map(|item| item.weight).collect();
+            let expected = bounded_knapsack(&slices, &counts, capacity).total_value;
+
+            let result = fractional_knapsack(&items, capacity);
+            assert!((expected - result.total_value).abs() < 1e-9);
+            assert!(result.fractions.iter().filter(|&&fraction| fraction < 1.0).count() <= 1);
+        }
+    }
+
+    #[test]
+    fn knapsack_2d_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(246);
+
+        for _ in 0..200 {
+            let count = rng.gen_range(0..8);
+            let items = random_items(&mut rng, count);
+            let volumes: Vec<usize> = items.iter().map(|_| rng.gen_range(0..8)).collect();
+            let (capacity, volume_capacity) = (rng.gen_range(0..20), rng.gen_range(0..20));
+
+            let result = knapsack_2d(&items, &volumes, capacity, volume_capacity);
+            let ones = vec![1; items.len()];
+            assert_eq!(brute_force_knapsack(&items, &ones, &volumes, capacity, volume_capacity), result.total_value);
+
+            let volume: usize = result.items.iter()
+                .map(|&item| volumes[items.iter().position(|other| std::ptr::eq(other, item)).unwrap()])
+                .sum();
+            assert!(result.total_weight <= capacity && volume <= volume_capacity);
+        }
+    }
+
+    #[test]
+    fn knapsack_by_value_test() {
+        let gigabyte = 1 << 30;
+        let items = vec![
+            KnapsackItem { value: 3.0, weight: 600 << 20 },
+            KnapsackItem { value: 4.0, weight: 700 << 20 },
+            KnapsackItem { value: 2.0, weight: 300 << 20 },
+            KnapsackItem { value: 2.0, weight: 100 << 20 }
+        ];
+
+        let result = knapsack_by_value(&items, gigabyte);
+        assert_eq!(7.0, result.total_value);
+        assert_eq!(vec![&items[0], &items[2], &items[3]], result.items);
+        assert_eq!(1000 << 20, result.total_weight);
+
+        let result = knapsack_branch_and_bound(&items, gigabyte);
+        assert_eq!(7.0, result.total_value);
+    }
+
+    #[test]
+    fn knapsack_exact_solvers_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(47);
+
+        for _ in 0..200 {
+            let count = rng.gen_range(0..10);
+            let items = random_items(&mut rng, count);
+            let capacity = rng.gen_range(0..30);
+            let expected = knapsack(&items, capacity).total_value;
+
+            for result in [knapsack_by_value(&items, capacity), knapsack_branch_and_bound(&items, capacity)] {
+                assert_eq!(expected, result.total_value);
+                assert!(result.total_weight <= capacity);
+            }
+        }
+    }
+
+    #[test]
+    fn knapsack_fptas_test() {
+        let mut rng = StdRng::seed_from_u64(147);
+
+        for _ in 0..100 {
+            let items: Vec<KnapsackItem> = (0..rng.gen_range(1..12))
+                .map(|_| KnapsackItem { value: rng.gen_range(1.0..1e6), weight: rng.gen_range(1..1_000_000_000) })
+                .collect();
+            let capacity = rng.gen_range(0..3_000_000_000);
+            let optimum = knapsack_branch_and_bound(&items, capacity).total_value;
+
+            for epsilon in [0.5, 0.1, 0.01] {
+                let result = knapsack_fptas(&items, capacity, epsilon);
+                assert!(result.total_weight <= capacity);
+                assert!(result.total_value >= (1.0 - epsilon) * optimum);
+                assert!(result.total_value <= optimum);
+            }
+        }
+    }
+
+    #[test]
+    fn knapsack_integer_cents_test() {
+        let dollars = vec![
+            KnapsackItem { value: 0.1, weight: 1 },
+            KnapsackItem { value: 0.2, weight: 1 },
+            KnapsackItem { value: 0.3, weight: 2 }
+        ];
+        assert_ne!(0.3, knapsack(&dollars, 2).total_value);
+
+        let items = vec![
+            KnapsackItem { value: 10u64, weight: 1 },
+            KnapsackItem { value: 20, weight: 1 },
+            KnapsackItem { value: 30, weight: 2 }
+        ];
+        assert_eq!(30, knapsack(&items, 2).total_value);
+        assert_eq!(60, knapsack(&items, 4).total_value);
+
+        let result = fractional_knapsack(&items, 1);
+        assert_eq!(20, result.total_value);
+        assert_eq!(15, fractional_knapsack(&items[2..], 1).total_value);
+    }
+
+    #[test]
+    fn knapsack_ratio_test() {
+        let items = vec![
+            KnapsackItem { value: Ratio::new(1, 3), weight: 2 },
+            KnapsackItem { value: Ratio::new(1, 2), weight: 3 },
+            KnapsackItem { value: Ratio::new(1, 4), weight: 2 }
+        ];
+
+        assert_eq!(Ratio::new(7, 12), knapsack(&items, 4).total_value);
+        assert_eq!(Ratio::new(5, 6), knapsack(&items, 5).total_value);
+        assert_eq!(Ratio::new(5, 6), knapsack_branch_and_bound(&items, 5).total_value);
+        assert_eq!(Ratio::new(2, 3), fractional_knapsack(&items, 4).total_value);
+        assert_eq!(Ratio::new(3, 2), unbounded_knapsack(&items, 9).total_value);
+    }
+
+    #[test]
+    fn knapsack_generic_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(48);
+
+        for _ in 0..200 {
+            let count = rng.gen_range(0..8);
+            let items = random_items(&mut rng, count);
+            let integer_items: Vec<KnapsackItem<i64>> = items.iter()
+                .map(|item| KnapsackItem { value: item.value as i64, weight: item.weight })
+                .collect();
+            let counts: Vec<usize> = items.iter().map(|_| rng.gen_range(0..4)).collect();
+            let capacity = rng.gen_range(0..30);
+
+            assert_eq!(knapsack(&items, capacity).total_value as i64, knapsack(&integer_items, capacity).total_value);
+            assert_eq!(unbounded_knapsack(&items, capacity).total_value as i64, unbounded_knapsack(&integer_items, capacity).total_value);
+            assert_eq!(bounded_knapsack(&items, &counts, capacity).total_value as i64,
+                       bounded_knapsack(&integer_items, &counts, capacity).total_value);
+            assert_eq!(knapsack_by_value(&items, capacity).total_value as i64, knapsack_by_value(&integer_items, capacity).total_value);
+            assert_eq!(knapsack_branch_and_bound(&items, capacity).total_value as i64,
+                       knapsack_branch_and_bound(&integer_items, capacity).total_value);
+        }
+    }
+
+    #[test]
+    fn knapsack_large_weights_test() {
+        // Values in cents, weights in bytes.
+        let items = vec![
+            KnapsackItem { value: 10_000_000_000u64, weight: 1 << 40 },
+            KnapsackItem { value: 9_000_000_000, weight: 1 << 39 },
+            KnapsackItem { value: u64::MAX / 4, weight: 1 << 42 }
+        ];
+
+        let result = fractional_knapsack(&items, 1 << 40);
+        assert_eq!(vec![&items[2]], result.items);
+        assert_eq!((1 << 60) - 1, result.total_value);
+        let result = fractional_knapsack(&items[..2], 1 << 40);
+        assert_eq!(vec![&items[1], &items[0]], result.items);
+        assert_eq!(9_000_000_000 + 5_000_000_000, result.total_value);
+
+        let result = knapsack_branch_and_bound(&items, (1 << 40) + (1 << 39));
+        assert_eq!(19_000_000_000, result.total_value);
+        assert_eq!(u64::MAX / 4, knapsack_branch_and_bound(&items, 1 << 42).total_value);
+
+        // The weights do not fit in the value type.
+        let items = vec![
+            KnapsackItem { value: 1_200_000_000i32, weight: 3_000_000_000 },
+            KnapsackItem { value: 600_000_000, weight: 1_000_000_000 }
+        ];
+        assert_eq!(600_000_000 + 800_000_000, fractional_knapsack(&items, 3_000_000_000).total_value);
+        assert_eq!(1_200_000_000, knapsack_branch_and_bound(&items, 3_000_000_000).total_value);
+        assert_eq!(1_800_000_000, knapsack_branch_and_bound(&items, 4_000_000_000).total_value);
+    }
+}
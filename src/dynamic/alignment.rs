@@ -0,0 +1,397 @@
+use std::ops::Range;
+use super::idx;
+
+/// Scores of aligning two residues against each other.
+pub trait SubstitutionMatrix {
+    fn score(&self, a: char, b: char) -> i32;
+}
+
+/// Scores every match and every mismatch the same, e.g. for nucleotides.
+pub struct SimpleScoring {
+    pub match_score: i32,
+    pub mismatch_score: i32
+}
+
+impl SubstitutionMatrix for SimpleScoring {
+    fn score(&self, a: char, b: char) -> i32 {
+        if a == b { self.match_score } else { self.mismatch_score }
+    }
+}
+
+/// Residue order of the rows and columns of `AminoAcidMatrix`, as in the NCBI tables.
+pub const AMINO_ACIDS: &str = "ARNDCQEGHILKMFPSTWYVBZX*";
+
+/// Amino acid substitution matrix over `AMINO_ACIDS`. Lowercase letters are scored as
+/// uppercase and unknown residues as `X`.
+pub struct AminoAcidMatrix(pub [[i32; 24]; 24]);
+
+impl SubstitutionMatrix for AminoAcidMatrix {
+    fn score(&self, a: char, b: char) -> i32 {
+        self.0[residue_index(a)][residue_index(b)]
+    }
+}
+
+fn residue_index(residue: char) -> usize {
+    AMINO_ACIDS.find(residue.to_ascii_uppercase()).unwrap_or(22)
+}
+
+pub const BLOSUM62: AminoAcidMatrix = AminoAcidMatrix([
+    [ 4, -1, -2, -2,  0, -1, -1,  0, -2, -1, -1, -1, -1, -2, -1,  1,  0, -3, -2,  0, -2, -1,  0, -4],
+    [-1,  5,  0, -2, -3,  1,  0, -2,  0, -3, -2,  2, -1, -3, -2, -1, -1, -3, -2, -3, -1,  0, -1, -4],
+    [-2,  0,  6,  1, -3,  0,  0,  0,  1, -3, -3,  0, -2, -3, -2,  1,  0, -4, -2, -3,  3,  0, -1, -4],
+    [-2, -2,  1,  6, -3,  0,  2, -1, -1, -3, -4, -1, -3, -3, -1,  0, -1, -4, -3, -3,  4,  1, -1, -4],
+    [ 0, -3, -3, -3,  9, -3, -4, -3, -3, -1, -1, -3, -1, -2, -3, -1, -1, -2, -2, -1, -3, -3, -2, -4],
+    [-1,  1,  0,  0, -3,  5,  2, -2,  0, -3, -2,  1,  0, -3, -1,  0, -1, -2, -1, -2,  0,  3, -1, -4],
+    [-1,  0,  0,  2, -4,  2,  5, -2,  0, -3, -3,  1, -2, -3, -1,  0, -1, -3, -2, -2,  1,  4, -1, -4],
+    [ 0, -2,  0, -1, -3, -2, -2,  6, -2, -4, -4, -2, -3, -3, -2,  0, -2, -2, -3, -3, -1, -2, -1, -4],
+    [-2,  0,  1, -1, -3,  0,  0, -2,  8, -3, -3, -1, -2, -1, -2, -1, -2, -2,  2, -3,  0,  0, -1, -4],
+    [-1, -3, -3, -3, -1, -3, -3, -4, -3,  4,  2, -3,  1,  0, -3, -2, -1, -3, -1,  3, -3, -3, -1, -4],
+    [-1, -2, -3, -4, -1, -2, -3, -4, -3,  2,  4, -2,  2,  0, -3, -2, -1, -2, -1,  1, -4, -3, -1, -4],
+    [-1,  2,  0, -1, -3,  1,  1, -2, -1, -3, -2,  5, -1, -3, -1,  0, -1, -3, -2, -2,  0,  1, -1, -4],
+    [-1, -1, -2, -3, -1,  0, -2, -3, -2,  1,  2, -1,  5,  0, -2, -1, -1, -1, -1,  1, -3, -1, -1, -4],
+    [-2, -3, -3, -3, -2, -3, -3, -3, -1,  0,  0, -3,  0,  6, -4, -2, -2,  1,  3, -1, -3, -3, -1, -4],
+    [-1, -2, -2, -1, -3, -1, -1, -2, -2, -3, -3, -1, -2, -4,  7, -1, -1, -4, -3, -2, -2, -1, -2, -4],
+    [ 1, -1,  1,  0, -1,  0,  0,  0, -1, -2, -2,  0, -1, -2, -1,  4,  1, -3, -2, -2,  0,  0,  0, -4],
+    [ 0, -1,  0, -1, -1, -1, -1, -2, -2, -1, -1, -1, -1, -2, -1,  1,  5, -2, -2,  0, -1, -1,  0, -4],
+    [-3, -3, -4, -4, -2, -2, -3, -2, -2, -3, -2, -3, -1,  1, -4, -3, -2, 11,  2, -3, -4, -3, -2, -4],
+    [-2, -2, -2, -3, -2, -1, -2, -3,  2, -1, -1, -2, -1,  3, -3, -2, -2,  2,  7, -1, -3, -2, -1, -4],
+    [ 0, -3, -3, -3, -1, -2, -2, -3, -3,  3,  1, -2,  1, -1, -2, -2,  0, -3, -1,  4, -3, -2, -1, -4],
+    [-2, -1,  3,  4, -3,  0,  1, -1,  0, -3, -4,  0, -3, -3, -2,  0, -1, -4, -3, -3,  4,  1, -1, -4],
+    [-1,  0,  0,  1, -3,  3,  4, -2,  0, -3, -3,  1, -1, -3, -1,  0, -1, -3, -2, -2,  1,  4, -1, -4],
+    [ 0, -1, -1, -1, -2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -2,  0,  0, -2, -1, -1, -1, -1, -1, -4],
+    [-4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4,  1]
+]);
+
+pub const PAM250: AminoAcidMatrix = AminoAcidMatrix([
+    [ 2, -2,  0,  0, -2,  0,  0,  1, -1, -1, -2, -1, -1, -3,  1,  1,  1, -6, -3,  0,  0,  0,  0, -8],
+    [-2,  6,  0, -1, -4,  1, -1, -3,  2, -2, -3,  3,  0, -4,  0,  0, -1,  2, -4, -2, -1,  0, -1, -8],
+    [ 0,  0,  2,  2, -4,  1,  1,  0,  2, -2, -3,  1, -2, -3,  0,  1,  0, -4, -2, -2,  2,  1,  0, -8],
+    [ 0, -1,  2,  4, -5,  2,  3,  1,  1, -2, -4,  0, -3, -6, -1,  0,  0, -7, -4, -2,  3,  3, -1, -8],
+    [-2, -4, -4, -5, 12, -5, -5, -3, -3, -2, -6, -5, -5, -4, -3,  0, -2, -8,  0, -2, -4, -5, -3, -8],
+    [ 0,  1,  1,  2, -5,  4,  2, -1,  3, -2, -2,  1, -1, -5,  0, -1, -1, -5, -4, -2,  1,  3, -1, -8],
+    [ 0, -1,  1,  3, -5,  2,  4,  0,  1, -2, -3,  0, -2, -5, -1,  0,  0, -7, -4, -2,  3,  3, -1, -8],
+    [ 1, -3,  0,  1, -3, -1,  0,  5, -2, -3, -4, -2, -3, -5,  0,  1,  0, -7, -5, -1,  0,  0, -1, -8],
+    [-1,  2,  2,  1, -3,  3,  1, -2,  6, -2, -2,  0, -2, -2,  0, -1, -1, -3,  0, -2,  1,  2, -1, -8],
+    [-1, -2, -2, -2, -2, -2, -2, -3, -2,  5,  2, -2,  2,  1, -2, -1,  0, -5, -1,  4, -2, -2, -1, -8],
+    [-2, -3, -3, -4, -6, -2, -3, -4, -2,  2,  6, -3,  4,  2, -3, -3, -2, -2, -1,  2, -3, -3, -1, -8],
+    [-1,  3,  1,  0, -5,  1,  0, -2,  0, -2, -3,  5,  0, -5, -1,  0,  0, -3, -4, -2,  1,  0, -1, -8],
+    [-1,  0, -2, -3, -5, -1, -2, -3, -2,  2,  4,  0,  6,  0, -2, -2, -1, -4, -2,  2, -2, -2, -1, -8],
+    [-3, -4, -3, -6, -4, -5, -5, -5, -2,  1,  2, -5,  0,  9, -5, -3, -3,  0,  7, -1, -4, -5, -2, -8],
+    [ 1,  0,  0, -1, -3,  0, -1,  0,  0, -2, -3, -1, -2, -5,  6,  1,  0, -6, -5, -1, -1,  0, -1, -8],
+    [ 1,  0,  1,  0,  0, -1,  0,  1, -1, -1, -3,  0, -2, -3,  1,  2,  1, -2, -3, -1,  0,  0,  0, -8],
+    [ 1, -1,  0,  0, -2, -1,  0,  0, -1,  0, -2,  0, -1, -3,  0,  1,  3, -5, -3,  0,  0, -1,  0, -8],
+    [-6,  2, -4, -7, -8, -5, -7, -7, -3, -5, -2, -3, -4,  0, -6, -2, -5, 17,  0, -6, -5, -6, -4, -8],
+    [-3, -4, -2, -4,  0, -4, -4, -5,  0, -1, -1, -4, -2,  7, -5, -3, -3,  0, 10, -2, -3, -4, -2, -8],
+    [ 0, -2, -2, -2, -2, -2, -2, -1, -2,  4,  2, -2,  2, -1, -1, -1,  0, -6, -2,  4, -2, -2, -1, -8],
+    [ 0, -1,  2,  3, -4,  1,  3,  0,  1, -2, -3,  1, -2, -4, -1,  0,  0, -5, -3, -2,  3,  2, -1, -8],
+    [ 0,  0,  1,  3, -5,  3,  3,  0,  2, -2, -3,  0, -2, -5,  0,  0, -1, -6, -4, -2,  2,  3, -1, -8],
+    [ 0, -1,  0, -1, -3, -1, -1, -1, -1, -1, -1, -1, -1, -2, -1,  0,  0, -4, -2, -1, -1, -1, -1, -8],
+    [-8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8,  1]
+]);
+
+/// Affine gap penalty (Gotoh): a gap of length `l` costs `open + extend * l`.
+#[derive(Debug, Clone, Copy)]
+pub struct GapPenalty {
+    pub open: i32,
+    pub extend: i32
+}
+
+/// Aligned sequences padded with `GAP` and the score of the alignment. The ranges give the
+/// aligned parts of the inputs, in characters; they cover the whole inputs for global alignment.
+/// The score is a sum of up to n + m `i32` scores, so it is kept in 64 bits.
+#[derive(Debug, PartialEq)]
+pub struct Alignment {
+    pub score: i64,
+    pub aligned_x: String,
+    pub aligned_y: String,
+    pub x_range: Range<usize>,
+    pub y_range: Range<usize>
+}
+
+/// Padding of the aligned sequences. The inputs must not contain it, or the gaps in the
+/// result could not be told apart from the residues.
+pub const GAP: char = '-';
+
+/// Global alignment (Needleman-Wunsch) with affine gaps. O(nm) time and space. The inputs must
+/// not contain `GAP`.
+pub fn needleman_wunsch<S: SubstitutionMatrix>(x: &str, y: &str, matrix: &S, gap: GapPenalty) -> Alignment {
+    align(x, y, matrix, gap, false)
+}
+
+/// Local alignment (Smith-Waterman) with affine gaps: the best scoring alignment of a
+/// substring of `x` and a substring of `y`. O(nm) time and space. The inputs must not contain
+/// `GAP`.
+pub fn smith_waterman<S: SubstitutionMatrix>(x: &str, y: &str, matrix: &S, gap: GapPenalty) -> Alignment {
+    align(x, y, matrix, gap, true)
+}
+
+// State of the alignment at a cell: the last column is a pair of residues, or a residue of
+// one sequence against a gap.
+#[derive(Copy, Clone, PartialEq)]
+enum AlignmentState {
+    Start,
+    Match,
+    GapInY,
+    GapInX
+}
+
+#[derive(Copy, Clone)]
+struct AlignmentData {
+    score: i64,
+    previous: AlignmentState
+}
+
+fn align<S: SubstitutionMatrix>(x: &str, y: &str, matrix: &S, gap: GapPenalty, local: bool) -> Alignment {
+    let x: Vec<char> = x.chars().collect();
+    let y: Vec<char> = y.chars().collect();
+    let n = x.len() + 1;
+    let open = gap.open as i64 + gap.extend as i64;
+    let extend = gap.extend as i64;
+
+    // Far enough from i64::MIN not to overflow when penalties are subtracted.
+    const NONE: AlignmentData = AlignmentData { score: i64::MIN / 4, previous: AlignmentState::Start };
+    let mut matches = vec![NONE; n * (y.len() + 1)];
+    let mut gaps_in_y = vec![NONE; n * (y.len() + 1)];
+    let mut gaps_in_x = vec![NONE; n * (y.len() + 1)];
+
+    matches[idx(0, 0, n)].score = 0;
+    if !local {
+        for i in 1..=x.len() {
+            gaps_in_y[idx(i, 0, n)] = AlignmentData {
+                score: -(gap.open as i64) - extend * i as i64,
+                previous: if i == 1 { AlignmentState::Match } else { AlignmentState::GapInY }
+            };
+        }
+        for j in 1..=y.len() {
+            gaps_in_x[idx(0, j, n)] = AlignmentData {
+                score: -(gap.open as i64) - extend * j as i64,
+                previous: if j == 1 { AlignmentState::Match } else { AlignmentState::GapInX }
+            };
+        }
+    }
+
+    fn best(candidates: &[(i64, AlignmentState)]) -> AlignmentData {
+        let mut result = AlignmentData { score: candidates[0].0, previous: candidates[0].1 };
+        for &(score, previous) in &candidates[1..] {
+            if score > result.score {
+                result = AlignmentData { score, previous };
+            }
+        }
+
+        result
+    }
+
+    let mut local_best = (0, 0, 0);
+    for j in 1..=y.len() {
+        for i in 1..=x.len() {
+            let diagonal = idx(i - 1, j - 1, n);
+            let substitution = matrix.score(x[i - 1], y[j - 1]) as i64;
+            let mut data = best(&[
+                (matches[diagonal].score, AlignmentState::Match),
+                (gaps_in_y[diagonal].score, AlignmentState::GapInY),
+                (gaps_in_x[diagonal].score, AlignmentState::GapInX)
+            ]);
+            // A local alignment may also start fresh at this pair.
+            if local && data.score < 0 {
+                data = AlignmentData { score: 0, previous: AlignmentState::Start };
+            }
+            data.score += substitution;
+            matches[idx(i, j, n)] = data;
+
+            let up = idx(i - 1, j, n);
+            gaps_in_y[idx(i, j, n)] = best(&[
+                (matches[up].score - open, AlignmentState::Match),
+                (gaps_in_y[up].score - extend, AlignmentState::GapInY),
+                (gaps_in_x[up].score - open, AlignmentState::GapInX)
+            ]);
+
+            let left = idx(i, j - 1, n);
+            gaps_in_x[idx(i, j, n)] = best(&[
+                (matches[left].score - open, AlignmentState::Match),
+                (gaps_in_x[left].score - extend, AlignmentState::GapInX),
+                (gaps_in_y[left].score - open, AlignmentState::GapInY)
+            ]);
+
+            if local && data.score > local_best.0 {
+                local_best = (data.score, i, j);
+            }
+        }
+    }
+
+    // A local alignment always ends with a pair of residues; an empty one scores 0.
+    let (score, mut i, mut j, mut state) = if local {
+        let (score, i, j) = local_best;
+        (score, i, j, if score > 0 { AlignmentState::Match } else { AlignmentState::Start })
+    }
+    else {
+        let end = idx(x.len(), y.len(), n);
+        let data = best(&[
+            (matches[end].score, AlignmentState::Match),
+            (gaps_in_y[end].score, AlignmentState::GapInY),
+            (gaps_in_x[end].score, AlignmentState::GapInX)
+        ]);
+        (data.score, x.len(), y.len(), data.previous)
+    };
+
+    // Walking back
+    let (x_end, y_end) = (i, j);
+    let mut aligned_x = Vec::new();
+    let mut aligned_y = Vec::new();
+
+    while state != AlignmentState::Start && (i > 0 || j > 0) {
+        let current = idx(i, j, n);
+        match state {
+            AlignmentState::Match => {
+                aligned_x.push(x[i - 1]);
+                aligned_y.push(y[j - 1]);
+                state = matches[current].previous;
+                i -= 1;
+                j -= 1;
+            },
+            AlignmentState::GapInY => {
+                aligned_x.push(x[i - 1]);
+                aligned_y.push(GAP);
+                state = gaps_in_y[current].previous;
+                i -= 1;
+            },
+            AlignmentState::GapInX => {
+                aligned_x.push(GAP);
+                aligned_y.push(y[j - 1]);
+                state = gaps_in_x[current].previous;
+                j -= 1;
+            },
+            AlignmentState::Start => unreachable!()
+        }
+    }
+
+    Alignment {
+        score,
+        aligned_x: aligned_x.into_iter().rev().collect(),
+        aligned_y: aligned_y.into_iter().rev().collect(),
+        x_range: i..x_end,
+        y_range: j..y_end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    fn rescore<S: SubstitutionMatrix>(alignment: &Alignment, matrix: &S, gap: GapPenalty) -> i64 {
+        let x: Vec<char> = alignment.aligned_x.chars().collect();
+        let y: Vec<char> = alignment.aligned_y.chars().collect();
+        let mut score = 0;
+
+        for k in 0..x.len() {
+            if x[k] == GAP || y[k] == GAP {
+                let opens = k == 0 || (x[k] == GAP) != (x[k - 1] == GAP) || (y[k] == GAP) != (y[k - 1] == GAP);
+                score -= gap.extend as i64 + if opens { gap.open as i64 } else { 0 };
+            }
+            else {
+                score += matrix.score(x[k], y[k]) as i64;
+            }
+        }
+
+        score
+    }
+
+    #[test]
+    fn substitution_matrices_test() {
+        for matrix in [&BLOSUM62, &PAM250] {
+            for a in 0..24 {
+                for b in 0..24 {
+                    assert_eq!(matrix.0[a][b], matrix.0[b][a]);
+                }
+            }
+        }
+
+        assert_eq!(11, BLOSUM62.score('W', 'W'));
+        assert_eq!(9, BLOSUM62.score('c', 'C'));
+        assert_eq!(BLOSUM62.score('X', 'W'), BLOSUM62.score('?', 'W'));
+        assert_eq!(17, PAM250.score('W', 'W'));
+        assert_eq!(12, PAM250.score('C', 'C'));
+    }
+
+    #[test]
+    fn needleman_wunsch_test() {
+        let scoring = SimpleScoring { match_score: 1, mismatch_score: -1 };
+        let gap = GapPenalty { open: 0, extend: 1 };
+        let result = needleman_wunsch("GATTACA", "GCATGCU", &scoring, gap);
+
+        assert_eq!(0, result.score);
+        assert_eq!(result.score, rescore(&result, &scoring, gap));
+        assert_eq!(0..7, result.x_range);
+
+        // With an expensive gap opening the deletion is kept in one piece.
+        let gap = GapPenalty { open: 10, extend: 1 };
+        let result = needleman_wunsch("HEAGAWGHEE", "HEAGHEE", &BLOSUM62, gap);
+        assert_eq!("HEAGAWGHEE", result.aligned_x);
+        assert!(result.aligned_y.contains("---"));
+        assert_eq!(3, result.aligned_y.matches(GAP).count());
+        assert_eq!(result.score, rescore(&result, &BLOSUM62, gap));
+    }
+
+    #[test]
+    fn smith_waterman_test() {
+        let gap = GapPenalty { open: 10, extend: 1 };
+        let result = smith_waterman("PAWHEAE", "HEAGAWGHEE", &BLOSUM62, gap);
+
+        assert_eq!("HEA", result.aligned_x);
+        assert_eq!("HEA", result.aligned_y);
+        assert_eq!(3..6, result.x_range);
+        assert_eq!(0..3, result.y_range);
+        assert_eq!(17, result.score);
+
+        let scoring = SimpleScoring { match_score: 1, mismatch_score: -1 };
+        let result = smith_waterman("AAA", "CCC", &scoring, gap);
+        assert_eq!(0, result.score);
+        assert_eq!("", result.aligned_x);
+    }
+
+    #[test]
+    fn large_score_test() {
+        // The scores do not fit in 32 bits.
+        let scoring = SimpleScoring { match_score: i32::MAX, mismatch_score: i32::MIN };
+        let gap = GapPenalty { open: i32::MAX, extend: i32::MAX };
+
+        let result = needleman_wunsch("AAAA", "AAAA", &scoring, gap);
+        assert_eq!(4 * i32::MAX as i64, result.score);
+        assert_eq!(result.score, rescore(&result, &scoring, gap));
+
+        let result = needleman_wunsch("AC", "CA", &scoring, gap);
+        assert_eq!(2 * i32::MIN as i64, result.score);
+
+        let result = smith_waterman("CAAAC", "GAAAG", &scoring, gap);
+        assert_eq!(3 * i32::MAX as i64, result.score);
+        assert_eq!(1..4, result.x_range);
+    }
+
+    #[test]
+    fn alignment_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let alphabet: Vec<char> = "ACGTż".chars().collect();
+
+        for _ in 0..200 {
+            let x: String = (0..rng.gen_range(0..15)).map(|_| alphabet[rng.gen_range(0..alphabet.len())]).collect();
+            let y: String = (0..rng.gen_range(0..15)).map(|_| alphabet[rng.gen_range(0..alphabet.len())]).collect();
+            let scoring = SimpleScoring { match_score: rng.gen_range(1..4), mismatch_score: -rng.gen_range(0..4) };
+            let gap = GapPenalty { open: rng.gen_range(0..5), extend: rng.gen_range(1..3) };
+
+            let global = needleman_wunsch(&x, &y, &scoring, gap);
+            assert_eq!(x, global.aligned_x.replace(GAP, ""));
+            assert_eq!(y, global.aligned_y.replace(GAP, ""));
+            assert_eq!(global.score, rescore(&global, &scoring, gap));
+
+            let local = smith_waterman(&x, &y, &scoring, gap);
+            // The ranges are in characters, not bytes.
+            let x_part: String = x.chars().skip(local.x_range.start).take(local.x_range.len()).collect();
+            let y_part: String = y.chars().skip(local.y_range.start).take(local.y_range.len()).collect();
+            assert_eq!(x_part, local.aligned_x.replace(GAP, ""));
+            assert_eq!(y_part, local.aligned_y.replace(GAP, ""));
+            assert_eq!(local.score, rescore(&local, &scoring, gap));
+            assert!(local.score >= global.score.max(0));
+        }
+    }
+}
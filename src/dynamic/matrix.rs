@@ -0,0 +1,114 @@
+use std::ops::{Add, Index, IndexMut, Mul};
+use super::{idx, MatrixSize};
+
+/// Dense matrix stored in row-major order.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Matrix<T> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>
+}
+
+impl<T> Matrix<T> where T: Copy + From<u8> + Add<Output=T> + Mul<Output=T> {
+    /// Matrix with the given rows, concatenated in `data`.
+    pub fn new(rows: usize, cols: usize, data: Vec<T>) -> Matrix<T> {
+        assert_eq!(rows * cols, data.len(), "data does not match the dimensions");
+        Matrix { rows, cols, data }
+    }
+
+    pub fn zeros(rows: usize, cols: usize) -> Matrix<T> {
+        Matrix { rows, cols, data: vec![T::from(0); rows * cols] }
+    }
+
+    pub fn identity(n: usize) -> Matrix<T> {
+        let mut result = Matrix::zeros(n, n);
+        for i in 0..n {
+            result[(i, i)] = T::from(1);
+        }
+
+        result
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn size(&self) -> MatrixSize {
+        MatrixSize { rows: self.rows, cols: self.cols }
+    }
+
+    pub fn transpose(&self) -> Matrix<T> {
+        let mut data = Vec::with_capacity(self.data.len());
+        for col in 0..self.cols {
+            for row in 0..self.rows {
+                data.push(self[(row, col)]);
+            }
+        }
+
+        Matrix { rows: self.cols, cols: self.rows, data }
+    }
+
+    /// Product `self * other`. Panics if the number of columns of `self` differs from the
+    /// number of rows of `other`.
+    pub fn multiply(&self, other: &Matrix<T>) -> Matrix<T> {
+        assert_eq!(self.cols, other.rows, "dimensions of the matrices do not agree");
+        let mut result = Matrix::zeros(self.rows, other.cols);
+
+        for row in 0..self.rows {
+            for k in 0..self.cols {
+                let value = self[(row, k)];
+                for col in 0..other.cols {
+                    result[(row, col)] = result[(row, col)] + value * other[(k, col)];
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    /// Element at `(row, col)`.
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(row < self.rows && col < self.cols, "index out of bounds");
+        &self.data[idx(col, row, self.cols)]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(row < self.rows && col < self.cols, "index out of bounds");
+        &mut self.data[idx(col, row, self.cols)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_test() {
+        let a = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let b = Matrix::new(3, 2, vec![7, 8, 9, 10, 11, 12]);
+
+        assert_eq!(Matrix::new(2, 2, vec![58, 64, 139, 154]), a.multiply(&b));
+        assert_eq!(Matrix::new(3, 2, vec![1, 4, 2, 5, 3, 6]), a.transpose());
+        assert_eq!(a, Matrix::identity(2).multiply(&a));
+        assert_eq!(a, a.multiply(&Matrix::identity(3)));
+        assert_eq!(6, a[(1, 2)]);
+        assert_eq!((3, 2), (a.transpose().rows(), a.transpose().cols()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn matrix_dimensions_test() {
+        let a: Matrix<f64> = Matrix::zeros(2, 3);
+        a.multiply(&a);
+    }
+}
@@ -0,0 +1,513 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use super::{matrix_mul, validate_chain, MatrixChainError, MatrixSize, MultiplicationOrder};
+
+/// Optimal order of multiplication of a chain of matrices in O(n log n), after Hu and Shing.
+///
+/// The chain is the polygon whose vertices are the dimensions of the matrices, and an order is
+/// a triangulation of it. Some optimal triangulation consists of potential h-arcs (arcs whose
+/// inner vertices are all heavier than both ends), with every region between them partitioned
+/// as a fan from its lightest vertex. For every arc, the cost of its subtree as a function of the
+/// weight of the fan vertex below is concave and piecewise linear; the breakpoints (the
+/// supporting weights of Hu and Shing) are kept in mergeable heaps.
+///
+/// Costs of subchains are kept in 128 bits and their products are compared exactly. Fails with
+/// `Overflow` if the optimum does not fit in `usize`, or if the cost of some subchain does not
+/// fit in 128 bits, which takes dimensions of about 2^40 or more.
+pub fn hu_shing(matrices: &[MatrixSize]) -> Result<MultiplicationOrder, MatrixChainError> {
+    validate_chain(matrices)?;
+    let n = matrices.len();
+    if n < 3 {
+        return matrix_mul(matrices);
+    }
+
+    let mut weights: Vec<usize> = matrices.iter().map(|matrix| matrix.rows).collect();
+    weights.push(matrices[n - 1].cols);
+
+    let diagonals = Polygon::new(&weights)?.optimal_diagonals()?;
+    order_from_triangulation(&weights, &diagonals)
+}
+
+// A sum of products of 128-bit numbers as its (top, high, low) 128-bit limbs, so that tuples
+// compare like the numbers.
+type Wide = (u128, u128, u128);
+
+fn product(a: u128, b: u128) -> Wide {
+    let mask = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & mask);
+    let (b_high, b_low) = (b >> 64, b & mask);
+
+    let (middle, middle_carry) = (a_high * b_low).overflowing_add(a_low * b_high);
+    let (low, low_carry) = (a_low * b_low).overflowing_add(middle << 64);
+    let high = a_high * b_high + (middle >> 64) + (u128::from(middle_carry) << 64) + u128::from(low_carry);
+
+    (0, high, low)
+}
+
+fn sum(a: Wide, b: Wide) -> Wide {
+    let (low, low_carry) = a.2.overflowing_add(b.2);
+    let (high, high_carry) = a.1.overflowing_add(b.1);
+    let (high, carry) = high.overflowing_add(u128::from(low_carry));
+
+    (a.0 + b.0 + u128::from(high_carry) + u128::from(carry), high, low)
+}
+
+fn checked(value: Option<u128>) -> Result<u128, MatrixChainError> {
+    value.ok_or(MatrixChainError::Overflow)
+}
+
+// An arc between two positions of the rotated polygon, with left < right.
+struct Arc {
+    left: usize,
+    right: usize,
+    // Lightest end, the fan vertex of the region above the arc when it is kept.
+    fan: usize,
+    children: Vec<usize>,
+    // Cost of everything above the arc when it is kept.
+    kept_cost: u128,
+    // The arc is kept if the weight of the fan vertex below it is at least
+    // `threshold.0 / threshold.1`.
+    threshold: Option<(u128, u128)>
+}
+
+// Breakpoint of a concave piecewise linear function at `shift / slope_change`: the slope to the
+// left of it is larger by `slope_change`.
+struct Breakpoint {
+    shift: u128,
+    slope_change: u128,
+    left: Option<usize>,
+    right: Option<usize>,
+    rank: usize
+}
+
+// Leftist max-heaps of breakpoints, stored in one arena.
+struct Breakpoints {
+    nodes: Vec<Breakpoint>
+}
+
+impl Breakpoints {
+    fn push(&mut self, heap: Option<usize>, shift: u128, slope_change: u128) -> Option<usize> {
+        self.nodes.push(Breakpoint { shift, slope_change, left: None, right: None, rank: 1 });
+        self.merge(heap, Some(self.nodes.len() - 1))
+    }
+
+    fn pop(&mut self, heap: usize) -> Option<usize> {
+        self.merge(self.nodes[heap].left, self.nodes[heap].right)
+    }
+
+    fn is_after(&self, a: usize, b: usize) -> bool {
+        product(self.nodes[a].shift, self.nodes[b].slope_change) > product(self.nodes[b].shift, self.nodes[a].slope_change)
+    }
+
+    fn rank(&self, heap: Option<usize>) -> usize {
+        heap.map_or(0, |heap| self.nodes[heap].rank)
+    }
+
+    fn merge(&mut self, a: Option<usize>, b: Option<usize>) -> Option<usize> {
+        let (a, b) = match (a, b) {
+            (None, heap) | (heap, None) => return heap,
+            (Some(a), Some(b)) => if self.is_after(b, a) { (b, a) } else { (a, b) }
+        };
+
+        let right = self.merge(self.nodes[a].right, Some(b));
+        self.nodes[a].right = right;
+        if self.rank(self.nodes[a].left) < self.rank(right) {
+            self.nodes[a].right = self.nodes[a].left;
+            self.nodes[a].left = right;
+        }
+        self.nodes[a].rank = self.rank(self.nodes[a].right) + 1;
+
+        Some(a)
+    }
+}
+
+// The polygon rotated so that the lightest vertex is at position 0; position `vertices` is
+// that vertex again, so that every arc is an interval of positions.
+struct Polygon {
+    start: usize,
+    vertices: usize,
+    weights: Vec<u128>,
+    // prefix[t] is the sum of products of the edges between positions 0 and t.
+    prefix: Vec<u128>,
+    // The root spans the whole polygon, the rest are the potential h-arcs, parents first.
+    arcs: Vec<Arc>
+}
+
+impl Polygon {
+    fn new(weights: &[usize]) -> Result<Polygon, MatrixChainError> {
+        let vertices = weights.len();
+        let start = (0..vertices).min_by_key(|&i| (weights[i], i)).unwrap();
+        let weights: Vec<u128> = (0..=vertices).map(|t| weights[(start + t) % vertices] as u128).collect();
+        let mut prefix = vec![0u128; vertices + 1];
+        for t in 0..vertices {
+            prefix[t + 1] = checked(prefix[t].checked_add(weights[t] * weights[t + 1]))?;
+        }
+
+        // Ties between weights are broken by position.
+        let key = |t: usize| if t == vertices { (weights[0], 0) } else { (weights[t], t) };
+        let arc = |left: usize, right: usize| Arc {
+            left,
+            right,
+            fan: if key(left) <= key(right) { left } else { right },
+            children: Vec::new(),
+            kept_cost: 0,
+            threshold: None
+        };
+
+        // One sweep: every vertex with lighter vertices on both sides spans a potential h-arc
+        // between its nearest lighter neighbours. Arcs that are sides of the polygon are skipped.
+        let mut arcs = Vec::new();
+        let mut stack = vec![0];
+        for t in 1..=vertices {
+            while stack.len() >= 2 && key(*stack.last().unwrap()) > key(t) {
+                stack.pop();
+                let left = *stack.last().unwrap();
+                let is_side = (left == 0 && t >= vertices - 1) || (left == 1 && t == vertices);
+                if !is_side {
+                    arcs.push(arc(left, t));
+                }
+            }
+            stack.push(t);
+        }
+
+        arcs.sort_by_key(|arc| (arc.left, Reverse(arc.right)));
+        arcs.insert(0, arc(0, vertices));
+
+        let mut open = vec![0];
+        for i in 1..arcs.len() {
+            while !(arcs[*open.last().unwrap()].left <= arcs[i].left && arcs[i].right <= arcs[*open.last().unwrap()].right) {
+                open.pop();
+            }
+            let parent = *open.last().unwrap();
+            arcs[parent].children.push(i);
+            open.push(i);
+        }
+
+        Ok(Polygon { start, vertices, weights, prefix, arcs })
+    }
+
+    fn is_incident(&self, arc: usize, vertex: usize) -> bool {
+        let arc = &self.arcs[arc];
+        arc.left % self.vertices == vertex % self.vertices || arc.right % self.vertices == vertex % self.vertices
+    }
+
+    fn chain(&self, arc: usize) -> u128 {
+        self.prefix[self.arcs[arc].right] - self.prefix[self.arcs[arc].left]
+    }
+
+    // Computes the cost functions bottom-up and the threshold of every arc.
+    fn compute_thresholds(&mut self) -> Result<(), MatrixChainError> {
+        let mut breakpoints = Breakpoints { nodes: Vec::new() };
+        // Slope and intercept of the last piece and the heap of breakpoints of every arc.
+        let mut functions: Vec<(u128, u128, Option<usize>)> = vec![(0, 0, None); self.arcs.len()];
+
+        for arc in (1..self.arcs.len()).rev() {
+            let (left, right, fan) = (self.arcs[arc].left, self.arcs[arc].right, self.arcs[arc].fan);
+            let fan_weight = self.weights[fan];
+            let children = std::mem::take(&mut self.arcs[arc].children);
+
+            // Sides of the region above the arc which are not covered by children.
+            let mut own = self.chain(arc);
+            for &child in &children {
+                own -= self.chain(child);
+            }
+            let mut not_incident = own;
+            if fan == left && children.iter().all(|&child| self.arcs[child].left != left) {
+                not_incident -= self.weights[left] * self.weights[left + 1];
+            }
+            if fan == right && children.iter().all(|&child| self.arcs[child].right != right) {
+                not_incident -= self.weights[right - 1] * self.weights[right];
+            }
+
+            let mut slope = own;
+            let mut intercept: u128 = 0;
+            let mut heap = None;
+            let mut kept_cost = checked(fan_weight.checked_mul(not_incident))?;
+
+            for &child in &children {
+                let (mut child_slope, mut child_intercept, mut child_heap) = functions[child];
+
+                // Only weights lighter than the fan vertex are ever passed to the child.
+                while let Some(top) = child_heap {
+                    let point = &breakpoints.nodes[top];
+                    if product(point.shift, 1) <= product(fan_weight, point.slope_change) {
+                        break;
+                    }
+                    child_slope = checked(child_slope.checked_add(point.slope_change))?;
+                    child_intercept -= point.shift;
+                    child_heap = breakpoints.pop(top);
+                }
+
+                // Arcs sharing the fan vertex are kept: it does not change the cost.
+                let child_cost = if self.is_incident(child, fan) {
+                    self.arcs[child].kept_cost
+                }
+                else {
+                    checked(child_slope.checked_mul(fan_weight).and_then(|cost| cost.checked_add(child_intercept)))?
+                };
+                kept_cost = checked(kept_cost.checked_add(child_cost))?;
+
+                slope = checked(slope.checked_add(child_slope))?;
+                intercept = checked(intercept.checked_add(child_intercept))?;
+                heap = breakpoints.merge(heap, child_heap);
+            }
+
+            // Removing the arc costs `slope * m + intercept`, keeping it `product * m + kept_cost`.
+            // Find where keeping becomes cheaper, dropping the breakpoints to the right of it.
+            let arc_product = self.weights[left] * self.weights[right];
+            while let Some(top) = heap {
+                let point = &breakpoints.nodes[top];
+                let removed = sum(product(slope, point.shift), product(intercept, point.slope_change));
+                let kept = sum(product(arc_product, point.shift), product(kept_cost, point.slope_change));
+                if removed < kept {
+                    break;
+                }
+                slope = checked(slope.checked_add(point.slope_change))?;
+                intercept -= point.shift;
+                heap = breakpoints.pop(top);
+            }
+
+            if slope > arc_product && product(kept_cost - intercept, 1) < product(fan_weight, slope - arc_product) {
+                let threshold = (kept_cost - intercept, slope - arc_product);
+                heap = breakpoints.push(heap, threshold.0, threshold.1);
+                self.arcs[arc].threshold = Some(threshold);
+                slope = arc_product;
+                intercept = kept_cost;
+            }
+
+            self.arcs[arc].children = children;
+            self.arcs[arc].kept_cost = kept_cost;
+            functions[arc] = (slope, intercept, heap);
+        }
+
+        Ok(())
+    }
+
+    // Diagonals of an optimal triangulation, as pairs of vertices of the original polygon.
+    fn optimal_diagonals(mut self) -> Result<Vec<(usize, usize)>, MatrixChainError> {
+        self.compute_thresholds()?;
+
+        let mut diagonals = Vec::new();
+        let mut regions = vec![0];
+        let mut next = 0;
+
+        while next < regions.len() {
+            let region = regions[next];
+            next += 1;
+            let fan = self.arcs[region].fan;
+
+            // Kept arcs directly above the region.
+            let mut frontier = Vec::new();
+            let mut stack = self.arcs[region].children.clone();
+            while let Some(arc) = stack.pop() {
+                let kept = self.is_incident(arc, fan) || self.arcs[arc].threshold
+                    .is_some_and(|(shift, slope_change)| product(self.weights[fan], slope_change) >= product(shift, 1));
+
+                if kept {
+                    frontier.push(arc);
+                    regions.push(arc);
+                    diagonals.push((self.arcs[arc].left, self.arcs[arc].right));
+                }
+                else {
+                    stack.extend(self.arcs[arc].children.iter().copied());
+                }
+            }
+            frontier.sort_by_key(|&arc| self.arcs[arc].left);
+
+            let (left, right) = (self.arcs[region].left, self.arcs[region].right);
+            let mut boundary = Vec::new();
+            let mut frontier = frontier.into_iter().peekable();
+            let mut t = left;
+            loop {
+                boundary.push(t);
+                if t == right {
+                    break;
+                }
+                t = match frontier.next_if(|&arc| self.arcs[arc].left == t) {
+                    Some(arc) => self.arcs[arc].right,
+                    None => t + 1
+                };
+            }
+
+            // The root closes the polygon, its last position is the first vertex again. If arcs
+            // from the first vertex cover both of its sides, they are the same diagonal and the
+            // root region is empty.
+            if region == 0 {
+                boundary.pop();
+            }
+            if boundary.len() < 3 {
+                continue;
+            }
+
+            // The fan from the lightest vertex, skipping its neighbours.
+            let fanned = if fan == right { &boundary[1..boundary.len() - 2] } else { &boundary[2..boundary.len() - 1] };
+            diagonals.extend(fanned.iter().map(|&vertex| (fan, vertex)));
+        }
+
+        let mut diagonals: Vec<(usize, usize)> = diagonals.into_iter()
+            .map(|(a, b)| ((self.start + a) % self.vertices, (self.start + b) % self.vertices))
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        diagonals.sort_unstable();
+        diagonals.dedup();
+        Ok(diagonals)
+    }
+}
+
+// Converts a triangulation of the polygon of weights into the order of multiplication: the
+// triangle on the side between the first and the last vertex splits the chain.
+fn order_from_triangulation(weights: &[usize], diagonals: &[(usize, usize)]) -> Result<MultiplicationOrder, MatrixChainError> {
+    let vertices = weights.len();
+    let mut neighbours = vec![Vec::new(); vertices];
+    for (i, list) in neighbours.iter_mut().enumerate().take(vertices - 1) {
+        list.push(i + 1);
+    }
+    for &(a, b) in diagonals {
+        neighbours[a].push(b);
+    }
+    for list in neighbours.iter_mut() {
+        list.sort_unstable();
+    }
+
+    let mut splits = HashMap::new();
+    let mut multiplications: usize = 0;
+    let mut stack = vec![(0, vertices - 1)];
+
+    while let Some((first, last)) = stack.pop() {
+        if last - first < 2 {
+            continue;
+        }
+
+        let list = &neighbours[first];
+        let apex = list[list.partition_point(|&vertex| vertex < last) - 1];
+        splits.insert((first, last - 1), apex - 1);
+
+        let cost = weights[first].checked_mul(weights[apex]).and_then(|product| product.checked_mul(weights[last]));
+        multiplications = cost.and_then(|cost| multiplications.checked_add(cost)).ok_or(MatrixChainError::Overflow)?;
+
+        stack.push((first, apex));
+        stack.push((apex, last));
+    }
+
+    #[allow(deprecated)]
+    Ok(MultiplicationOrder { multiplications, matrices_count: vertices - 1, order: Vec::new(), splits })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    fn chain(dimensions: &[usize]) -> Vec<MatrixSize> {
+        dimensions.windows(2).map(|pair| MatrixSize { rows: pair[0], cols: pair[1] }).collect()
+    }
+
+    // Cost of the order given by the splits.
+    fn order_cost(dimensions: &[usize], order: &MultiplicationOrder) -> u128 {
+        let mut cost = 0;
+        let mut stack = vec![(0, dimensions.len() - 2)];
+        while let Some((left, right)) = stack.pop() {
+            if left < right {
+                let split = order.split(left, right).unwrap();
+                cost += dimensions[left] as u128 * dimensions[split + 1] as u128 * dimensions[right + 1] as u128;
+                stack.push((left, split));
+                stack.push((split + 1, right));
+            }
+        }
+
+        cost
+    }
+
+    #[test]
+    fn hu_shing_test() {
+        let matrices = chain(&[30, 35, 15, 5, 10, 20, 25]);
+        let result = hu_shing(&matrices).unwrap();
+
+        assert_eq!(15125, result.multiplications);
+        assert_eq!("((A0*(A1*A2))*((A3*A4)*A5))", result.to_string());
+
+        assert_eq!(0, hu_shing(&[]).unwrap().multiplications);
+        assert_eq!("A0", hu_shing(&chain(&[2, 3])).unwrap().to_string());
+        let mismatched = [MatrixSize { rows: 1, cols: 2 }, MatrixSize { rows: 2, cols: 3 }, MatrixSize { rows: 4, cols: 5 }];
+        assert_eq!(Some(MatrixChainError::DimensionMismatch(1)), hu_shing(&mismatched).err());
+        assert_eq!(Some(MatrixChainError::Overflow), hu_shing(&chain(&[1 << 30, 1 << 30, 1 << 30, 1 << 30])).err());
+    }
+
+    #[test]
+    fn hu_shing_large_dimensions_test() {
+        // The optimum is small although the worst order is not.
+        let dimensions: Vec<usize> = (0..=20).map(|i| if i % 2 == 0 { 1 } else { 1 << 20 }).collect();
+        let matrices = chain(&dimensions);
+        let expected = matrix_mul(&matrices).unwrap();
+        let result = hu_shing(&matrices).unwrap();
+
+        assert_eq!(10_485_769, result.multiplications);
+        assert_eq!(expected.multiplications, result.multiplications);
+        assert_eq!(result.multiplications as u128, order_cost(&dimensions, &result));
+
+        // Costs of other orders need more than 64 bits.
+        let dimensions: Vec<usize> = (0..=20).map(|i| if i % 2 == 0 { 1 } else { 1 << 40 }).collect();
+        let result = hu_shing(&chain(&dimensions)).unwrap();
+        assert_eq!(matrix_mul(&chain(&dimensions)).unwrap().multiplications, result.multiplications);
+        assert_eq!(result.multiplications as u128, order_cost(&dimensions, &result));
+
+        let mut rng = StdRng::seed_from_u64(245);
+        for _ in 0..100 {
+            let length = rng.gen_range(3..60);
+            let dimensions: Vec<usize> = (0..=length).map(|_| rng.gen_range(1..=2_000_000)).collect();
+            let matrices = chain(&dimensions);
+
+            let expected = matrix_mul(&matrices).map(|order| order.multiplications);
+            assert_eq!(expected, hu_shing(&matrices).map(|order| order.multiplications), "{:?}", dimensions);
+        }
+
+        // Too long for the cubic algorithm, which the large dimensions used to fall back to.
+        let dimensions: Vec<usize> = (0..=3000).map(|_| rng.gen_range(1..=2_000_000)).collect();
+        let result = hu_shing(&chain(&dimensions)).unwrap();
+        let sequential: u128 = (1..3000).map(|k| (dimensions[0] * dimensions[k]) as u128 * dimensions[k + 1] as u128).sum();
+        assert!((result.multiplications as u128) <= sequential);
+    }
+
+    #[test]
+    fn hu_shing_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(45);
+
+        for round in 0..2000 {
+            let length = rng.gen_range(1..14);
+            let largest = if round % 2 == 0 { 6 } else { 100 };
+            let dimensions: Vec<usize> = (0..=length).map(|_| rng.gen_range(1..=largest)).collect();
+            let matrices = chain(&dimensions);
+
+            let expected = matrix_mul(&matrices).unwrap();
+            let result = hu_shing(&matrices).unwrap();
+            assert_eq!(expected.multiplications, result.multiplications, "{:?}", dimensions);
+
+            // The order is complete and its cost is the one reported.
+            assert_eq!(result.multiplications as u128, order_cost(&dimensions, &result));
+        }
+    }
+
+    #[test]
+    fn wide_arithmetic_test() {
+        assert_eq!((0, u128::MAX - 1, 1), product(u128::MAX, u128::MAX));
+        assert_eq!((0, 0, 6), product(2, 3));
+        assert_eq!((0, 1, 0), product(1 << 64, 1 << 64));
+        assert_eq!((1, u128::MAX - 3, 2), sum(product(u128::MAX, u128::MAX), product(u128::MAX, u128::MAX)));
+        assert_eq!((0, 1, 0), sum((0, 0, u128::MAX), (0, 0, 1)));
+    }
+
+    #[test]
+    fn hu_shing_long_chain_test() {
+        let mut rng = StdRng::seed_from_u64(145);
+        let dimensions: Vec<usize> = (0..=5000).map(|_| rng.gen_range(1..1000)).collect();
+        let result = hu_shing(&chain(&dimensions)).unwrap();
+
+        assert_eq!(5000, result.matrices_count);
+        assert_eq!(4999, result.to_string().matches('*').count());
+
+        // No worse than multiplying from left to right.
+        let sequential: usize = (1..5000).map(|k| dimensions[0] * dimensions[k] * dimensions[k + 1]).sum();
+        assert!(result.multiplications <= sequential);
+    }
+}
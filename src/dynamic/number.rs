@@ -0,0 +1,345 @@
+use std::{fmt, cmp::Ordering};
+use std::ops::{Add, Sub, Mul, Div};
+
+/// Values the dynamic programming solvers can add, compare and scale by counts: integers
+/// (exact, e.g. prices in cents), floats and `Ratio`.
+pub trait Number: Copy + PartialOrd + fmt::Debug
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    fn zero() -> Self;
+
+    /// Converts a count or a weight; panics if it does not fit.
+    fn from_usize(n: usize) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    /// The value as a count, None if it is negative or not an integer.
+    fn to_usize(self) -> Option<usize>;
+
+    /// Compares `self * factor` with `other * other_factor` without overflowing, e.g. the
+    /// values per unit of weight `self / other_factor` and `other / factor`.
+    fn cmp_scaled(self, factor: usize, other: Self, other_factor: usize) -> Ordering;
+
+    /// `self * numerator / denominator` for `numerator <= denominator` without overflowing.
+    /// Integers are rounded toward zero.
+    fn scale(self, numerator: usize, denominator: usize) -> Self;
+}
+
+// Product of a 128-bit and a 64-bit number as its high and low 128 bits.
+fn wide_mul(a: u128, b: usize) -> (u128, u128) {
+    let b = b as u128;
+    let low = (a & u64::MAX as u128) * b;
+    let high = (a >> 64) * b;
+    let (result, carry) = (high << 64).overflowing_add(low);
+
+    ((high >> 64) + u128::from(carry), result)
+}
+
+// Compares `a * a_factor` with `b * b_factor` for numbers given by their sign and magnitude.
+fn cmp_signed(a_negative: bool, a: u128, a_factor: usize, b_negative: bool, b: u128, b_factor: usize) -> Ordering {
+    let a_zero = a == 0 || a_factor == 0;
+    let b_zero = b == 0 || b_factor == 0;
+    let a_sign = if a_zero { 0 } else if a_negative { -1 } else { 1 };
+    let b_sign = if b_zero { 0 } else if b_negative { -1 } else { 1 };
+
+    match a_sign.cmp(&b_sign) {
+        Ordering::Equal => {
+            let magnitudes = wide_mul(a, a_factor).cmp(&wide_mul(b, b_factor));
+            if a_sign < 0 { magnitudes.reverse() } else { magnitudes }
+        },
+        different => different
+    }
+}
+
+// `a * numerator / denominator` rounded down, for `numerator <= denominator`.
+fn scale_magnitude(a: u128, numerator: usize, denominator: usize) -> u128 {
+    assert!(numerator <= denominator, "the numerator must not exceed the denominator");
+    let (numerator, denominator) = (numerator as u128, denominator as u128);
+
+    // Neither product can overflow: the quotient part is at most `a`, and the remainder
+    // and the numerator are both below 2^64.
+    (a / denominator) * numerator + (a % denominator) * numerator / denominator
+}
+
+macro_rules! integer_number {
+    ($($t:ty),*; signed) => {$(
+        integer_number!($t, |value: $t| (value < 0, value.unsigned_abs() as u128),
+                        |negative: bool, magnitude: u128| if negative { (magnitude as $t).wrapping_neg() } else { magnitude as $t });
+    )*};
+    ($($t:ty),*; unsigned) => {$(
+        integer_number!($t, |value: $t| (false, value as u128), |_: bool, magnitude: u128| magnitude as $t);
+    )*};
+    ($t:ty, $split:expr, $join:expr) => {
+        impl Number for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn from_usize(n: usize) -> Self {
+                Self::try_from(n).expect("value out of range")
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn to_usize(self) -> Option<usize> {
+                usize::try_from(self).ok()
+            }
+
+            fn cmp_scaled(self, factor: usize, other: Self, other_factor: usize) -> Ordering {
+                let ((negative, magnitude), (other_negative, other_magnitude)) = ($split(self), $split(other));
+                cmp_signed(negative, magnitude, factor, other_negative, other_magnitude, other_factor)
+            }
+
+            fn scale(self, numerator: usize, denominator: usize) -> Self {
+                // The result is not larger than `self`, so it fits.
+                let (negative, magnitude) = $split(self);
+                $join(negative, scale_magnitude(magnitude, numerator, denominator))
+            }
+        }
+    }
+}
+
+integer_number!(i8, i16, i32, i64, i128, isize; signed);
+integer_number!(u8, u16, u32, u64, u128, usize; unsigned);
+
+macro_rules! float_number {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            fn zero() -> Self {
+                0.0
+            }
+
+            fn from_usize(n: usize) -> Self {
+                n as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn to_usize(self) -> Option<usize> {
+                if self >= 0.0 && self.fract() == 0.0 && (self as f64) <= usize::MAX as f64 { Some(self as usize) } else { None }
+            }
+
+            fn cmp_scaled(self, factor: usize, other: Self, other_factor: usize) -> Ordering {
+                (self * factor as $t).partial_cmp(&(other * other_factor as $t)).unwrap_or(Ordering::Equal)
+            }
+
+            fn scale(self, numerator: usize, denominator: usize) -> Self {
+                self * (numerator as $t / denominator as $t)
+            }
+        }
+    )*}
+}
+
+float_number!(f32, f64);
+
+/// Exact rational number, always in lowest terms with a positive denominator. Panics when a
+/// result does not fit into `i64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numerator: i64,
+    denominator: i64
+}
+
+impl Ratio {
+    pub fn new(numerator: i64, denominator: i64) -> Ratio {
+        Ratio::reduced(numerator as i128, denominator as i128)
+    }
+
+    pub fn integer(n: i64) -> Ratio {
+        Ratio { numerator: n, denominator: 1 }
+    }
+
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+
+    fn reduced(mut numerator: i128, mut denominator: i128) -> Ratio {
+        assert!(denominator != 0, "zero denominator");
+
+        if denominator < 0 {
+            numerator = -numerator;
+            denominator = -denominator;
+        }
+        let divisor = gcd(numerator.unsigned_abs(), denominator.unsigned_abs()) as i128;
+
+        Ratio {
+            numerator: i64::try_from(numerator / divisor).expect("rational overflow"),
+            denominator: i64::try_from(denominator / divisor).expect("rational overflow")
+        }
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+impl Add for Ratio {
+    type Output = Ratio;
+
+    fn add(self, other: Ratio) -> Ratio {
+        Ratio::reduced(self.numerator as i128 * other.denominator as i128 + other.numerator as i128 * self.denominator as i128,
+                       self.denominator as i128 * other.denominator as i128)
+    }
+}
+
+impl Sub for Ratio {
+    type Output = Ratio;
+
+    fn sub(self, other: Ratio) -> Ratio {
+        Ratio::reduced(self.numerator as i128 * other.denominator as i128 - other.numerator as i128 * self.denominator as i128,
+                       self.denominator as i128 * other.denominator as i128)
+    }
+}
+
+impl Mul for Ratio {
+    type Output = Ratio;
+
+    fn mul(self, other: Ratio) -> Ratio {
+        Ratio::reduced(self.numerator as i128 * other.numerator as i128, self.denominator as i128 * other.denominator as i128)
+    }
+}
+
+impl Div for Ratio {
+    type Output = Ratio;
+
+    fn div(self, other: Ratio) -> Ratio {
+        Ratio::reduced(self.numerator as i128 * other.denominator as i128, self.denominator as i128 * other.numerator as i128)
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, other: &Ratio) -> Ordering {
+        (self.numerator as i128 * other.denominator as i128).cmp(&(other.numerator as i128 * self.denominator as i128))
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Ratio) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        }
+        else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl Number for Ratio {
+    fn zero() -> Self {
+        Ratio::integer(0)
+    }
+
+    fn from_usize(n: usize) -> Self {
+        Ratio::integer(i64::try_from(n).expect("value out of range"))
+    }
+
+    fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    fn to_usize(self) -> Option<usize> {
+        if self.denominator == 1 { usize::try_from(self.numerator).ok() } else { None }
+    }
+
+    fn cmp_scaled(self, factor: usize, other: Self, other_factor: usize) -> Ordering {
+        // Over the common denominator; both cross products fit in i128.
+        let left = self.numerator as i128 * other.denominator as i128;
+        let right = other.numerator as i128 * self.denominator as i128;
+        cmp_signed(left < 0, left.unsigned_abs(), factor, right < 0, right.unsigned_abs(), other_factor)
+    }
+
+    fn scale(self, numerator: usize, denominator: usize) -> Self {
+        assert!(numerator <= denominator, "the numerator must not exceed the denominator");
+        let divisor = gcd(numerator as u128, denominator as u128).max(1);
+        let (numerator, denominator) = ((numerator as u128 / divisor) as i128, (denominator as u128 / divisor) as i128);
+
+        Ratio::reduced((self.numerator as i128).checked_mul(numerator).expect("rational overflow"),
+                       (self.denominator as i128).checked_mul(denominator).expect("rational overflow"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratio_arithmetic_test() {
+        let tenth = Ratio::new(1, 10);
+        let fifth = Ratio::new(-2, -10);
+
+        assert_eq!(Ratio::new(1, 5), fifth);
+        assert_eq!(Ratio::new(3, 10), tenth + fifth);
+        assert_eq!(Ratio::new(-1, 10), tenth - fifth);
+        assert_eq!(Ratio::new(1, 50), tenth * fifth);
+        assert_eq!(Ratio::new(1, 2), tenth / fifth);
+        assert_eq!(Ratio::new(-3, 4), Ratio::new(3, -4));
+        assert_eq!(Ratio::zero(), Ratio::new(0, 7));
+        assert_eq!(1, Ratio::zero().denominator());
+    }
+
+    #[test]
+    fn ratio_order_and_display_test() {
+        assert!(Ratio::new(1, 3) < Ratio::new(1, 2));
+        assert!(Ratio::new(-1, 2) < Ratio::zero());
+        assert_eq!("3/4", Ratio::new(6, 8).to_string());
+        assert_eq!("-2", Ratio::new(4, -2).to_string());
+        assert_eq!(0.75, Ratio::new(3, 4).to_f64());
+    }
+
+    #[test]
+    fn to_usize_test() {
+        assert_eq!(Some(3), 3i64.to_usize());
+        assert_eq!(None, (-3i64).to_usize());
+        assert_eq!(Some(4), 4.0f64.to_usize());
+        assert_eq!(None, 4.5f64.to_usize());
+        assert_eq!(Some(2), Ratio::new(6, 3).to_usize());
+        assert_eq!(None, Ratio::new(1, 3).to_usize());
+    }
+
+    #[test]
+    fn cmp_scaled_test() {
+        assert_eq!(Ordering::Less, 10_000_000_000u64.cmp_scaled(1 << 40, u64::MAX, 1 << 41));
+        assert_eq!(Ordering::Equal, u128::MAX.cmp_scaled(usize::MAX, u128::MAX, usize::MAX));
+        assert_eq!(Ordering::Greater, u128::MAX.cmp_scaled(usize::MAX, u128::MAX - 1, usize::MAX));
+        assert_eq!(Ordering::Less, i128::MIN.cmp_scaled(usize::MAX, i128::MIN + 1, usize::MAX));
+        assert_eq!(Ordering::Less, (-1i32).cmp_scaled(1, 0, 5));
+        assert_eq!(Ordering::Equal, (-3i32).cmp_scaled(0, 7, 0));
+        assert_eq!(Ordering::Greater, 2.5f64.cmp_scaled(2, 1.0, 4));
+        assert_eq!(Ordering::Less, Ratio::new(-1, 3).cmp_scaled(usize::MAX, Ratio::new(-1, 2), 1));
+        assert_eq!(Ordering::Equal, Ratio::new(1, 3).cmp_scaled(3, Ratio::integer(1), 1));
+    }
+
+    #[test]
+    fn scale_test() {
+        assert_eq!(i32::MAX / 3, i32::MAX.scale(1_000_000_000, 3_000_000_000));
+        assert_eq!(-2, (-5i32).scale(1, 2));
+        assert_eq!(i128::MIN, i128::MIN.scale(7, 7));
+        assert_eq!(u128::MAX / 2, u128::MAX.scale(1, 2));
+        assert_eq!(u128::MAX / usize::MAX as u128 * (usize::MAX as u128 - 1), u128::MAX.scale(usize::MAX - 1, usize::MAX));
+        assert_eq!(1.25, 2.5f64.scale(1, 2));
+        assert_eq!(Ratio::new(1, 9), Ratio::new(1, 3).scale(1_000_000_000, 3_000_000_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "zero denominator")]
+    fn zero_denominator_test() {
+        Ratio::new(1, 0);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::bst::Tree;
+use super::idx;
+use super::number::Number;
+
+/// Optimal binary search tree over keys `0..n` (CLRS 15.5).
+pub struct OptimalBst<T> {
+    /// Expected number of nodes visited by a search, weighted by the probabilities.
+    pub expected_cost: T,
+    size: usize,
+    // roots[idx(i, j, size + 1)] is the root of the subtree over the keys i..j.
+    roots: Vec<usize>
+}
+
+impl<T> OptimalBst<T> {
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// The root of the optimal subtree over the keys `from..to`, None if the range is empty.
+    pub fn root(&self, from: usize, to: usize) -> Option<usize> {
+        assert!(from <= to && to <= self.size, "invalid key range");
+
+        if from == to { None } else { Some(self.roots[idx(from, to, self.size + 1)]) }
+    }
+
+    /// Builds the tree with `keys[i]` for key `i`. Keys must be strictly increasing.
+    pub fn to_tree<K: Copy + PartialOrd>(&self, keys: &[K]) -> Tree<K> {
+        assert_eq!(self.size, keys.len(), "every key needs a value");
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]), "keys must be strictly increasing");
+
+        // Inserting every parent before its children reproduces the shape.
+        let mut tree = Tree::new();
+        let mut ranges = vec![(0, self.size)];
+        while let Some((from, to)) = ranges.pop() {
+            if let Some(root) = self.root(from, to) {
+                tree.insert(keys[root]);
+                ranges.push((from, root));
+                ranges.push((root + 1, to));
+            }
+        }
+
+        tree
+    }
+}
+
+/// Finds the binary search tree with the minimum expected search cost, where `p[i]` is the
+/// probability (or frequency) of searching key `i` and `q[i]` of searching between keys
+/// `i - 1` and `i`. A search costs the depth of the node or gap reached plus one. Knuth's
+/// monotonicity of the roots gives O(n²) time.
+pub fn optimal_bst<T: Number>(p: &[T], q: &[T]) -> OptimalBst<T> {
+    assert_eq!(p.len() + 1, q.len(), "there must be one gap more than keys");
+
+    let size = p.len();
+    let n = size + 1;
+    // Expected costs and total weights of the subtrees over the keys i..j.
+    let mut costs = vec![T::zero(); n * n];
+    let mut weights = vec![T::zero(); n * n];
+    let mut roots = vec![0; n * n];
+    for i in 0..n {
+        costs[idx(i, i, n)] = q[i];
+        weights[idx(i, i, n)] = q[i];
+    }
+
+    for length in 1..=size {
+        for i in 0..=(size - length) {
+            let j = i + length;
+            let weight = weights[idx(i, j - 1, n)] + p[j - 1] + q[j];
+            weights[idx(i, j, n)] = weight;
+
+            let (first, last) = if length == 1 { (i, i) } else { (roots[idx(i, j - 1, n)], roots[idx(i + 1, j, n)]) };
+            let mut best = None;
+            for r in first..=last {
+                let cost = costs[idx(i, r, n)] + costs[idx(r + 1, j, n)] + weight;
+                if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                    best = Some((cost, r));
+                }
+            }
+
+            let (cost, root) = best.unwrap();
+            costs[idx(i, j, n)] = cost;
+            roots[idx(i, j, n)] = root;
+        }
+    }
+
+    OptimalBst { expected_cost: costs[idx(0, size, n)], size, roots }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bst::Node;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    // Cost of searching every key and gap in the tree, the root having depth 1.
+    fn tree_cost(node: Option<&Node<usize>>, depth: u64, p: &[u64], q: &[u64], from: usize, to: usize) -> u64 {
+        match node {
+            None => {
+                assert_eq!(from, to);
+                depth * q[from]
+            },
+            Some(node) => {
+                depth * p[node.value]
+                    + tree_cost(node.left(), depth + 1, p, q, from, node.value)
+                    + tree_cost(node.right(), depth + 1, p, q, node.value + 1, to)
+            }
+        }
+    }
+
+    // The O(n³) dynamic programming without Knuth's optimization.
+    fn cubic_cost(p: &[u64], q: &[u64]) -> u64 {
+        let n = p.len() + 1;
+        let mut costs = vec![0; n * n];
+        for i in 0..n {
+            costs[idx(i, i, n)] = q[i];
+        }
+
+        for length in 1..n {
+            for i in 0..(n - length) {
+                let j = i + length;
+                let weight: u64 = p[i..j].iter().sum::<u64>() + q[i..=j].iter().sum::<u64>();
+                costs[idx(i, j, n)] = (i..j).map(|r| costs[idx(i, r, n)] + costs[idx(r + 1, j, n)]).min().unwrap() + weight;
+            }
+        }
+
+        costs[idx(0, n - 1, n)]
+    }
+
+    #[test]
+    fn optimal_bst_test() {
+        // CLRS figure 15.9 with probabilities in hundredths.
+        let p = [15, 10, 5, 10, 20];
+        let q = [5, 10, 5, 5, 5, 10];
+        let result = optimal_bst(&p, &q);
+
+        assert_eq!(275, result.expected_cost);
+        assert_eq!(5, result.len());
+        assert_eq!(Some(1), result.root(0, 5));
+        assert_eq!(Some(0), result.root(0, 1));
+        assert_eq!(Some(4), result.root(2, 5));
+        assert_eq!(Some(3), result.root(2, 4));
+        assert_eq!(None, result.root(3, 3));
+
+        let tree = result.to_tree(&["a", "b", "c", "d", "e"]);
+        assert_eq!(vec!["a", "b", "c", "d", "e"], tree.iter().collect::<Vec<_>>());
+        let root = tree.root.as_deref().unwrap();
+        assert_eq!("b", root.value);
+        assert_eq!("a", root.left().unwrap().value);
+        assert_eq!("e", root.right().unwrap().value);
+    }
+
+    #[test]
+    fn optimal_bst_float_test() {
+        let result = optimal_bst::<f64>(&[0.15, 0.10, 0.05, 0.10, 0.20], &[0.05, 0.10, 0.05, 0.05, 0.05, 0.10]);
+        assert!((result.expected_cost - 2.75).abs() < 1e-9);
+
+        let result = optimal_bst::<f64>(&[], &[1.0]);
+        assert!(result.is_empty());
+        assert_eq!(1.0, result.expected_cost);
+        assert!(result.to_tree::<i32>(&[]).root.is_none());
+    }
+
+    #[test]
+    fn optimal_bst_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(50);
+
+        for _ in 0..300 {
+            let size = rng.gen_range(0..12);
+            let p: Vec<u64> = (0..size).map(|_| if rng.gen_bool(0.2) { 0 } else { rng.gen_range(0..100) }).collect();
+            let q: Vec<u64> = (0..=size).map(|_| if rng.gen_bool(0.2) { 0 } else { rng.gen_range(0..100) }).collect();
+
+            let result = optimal_bst(&p, &q);
+            assert_eq!(cubic_cost(&p, &q), result.expected_cost);
+
+            let keys: Vec<usize> = (0..size).collect();
+            let tree = result.to_tree(&keys);
+            assert_eq!(keys, tree.iter().collect::<Vec<_>>());
+            assert_eq!(result.expected_cost, tree_cost(tree.root.as_deref(), 1, &p, &q, 0, size));
+        }
+    }
+}
@@ -1,7 +1,11 @@
 pub mod bst;
+pub mod diff;
 pub mod disjoint_sets;
 pub mod dynamic;
 pub mod list;
 pub mod patterns;
 pub mod sort;
 pub mod stats;
+pub mod suffix_array;
+pub mod suffix_automaton;
+pub mod trie;
@@ -1,3 +1,354 @@
-fn main() {
-    println!("Hello, world!");
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use cormen_rust::patterns::{KmpMatcher, Matcher, NaiveMatcher};
+
+const USAGE: &str = "\
+Usage: cormen find [OPTIONS] PATTERN [PATH...]
+
+Searches files, directories (recursively) or the standard input for PATTERN
+and prints line:column of every match. Symbolic links inside of directories
+are skipped; unreadable paths are reported and skipped.
+
+Options:
+  -a, --algorithm <naive|kmp>  Matching algorithm (default: kmp)
+  -c, --count                  Print only the number of matches per input
+  -o, --only-matching          Print only the matched pattern instead of the line
+  -h, --help                   Print this help";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Algorithm {
+    Naive,
+    Kmp
+}
+
+#[derive(Debug, PartialEq)]
+struct FindOptions {
+    pattern: String,
+    paths: Vec<PathBuf>,
+    algorithm: Algorithm,
+    count: bool,
+    only_matching: bool
+}
+
+fn parse_find_options<I: Iterator<Item = String>>(mut args: I) -> Result<FindOptions, String> {
+    let mut pattern = None;
+    let mut paths = Vec::new();
+    let mut algorithm = Algorithm::Kmp;
+    let mut count = false;
+    let mut only_matching = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-a" | "--algorithm" => {
+                algorithm = match args.next().as_deref() {
+                    Some("naive") => Algorithm::Naive,
+                    Some("kmp") => Algorithm::Kmp,
+                    Some(other) => return Err(format!("unknown algorithm '{}'", other)),
+                    None => return Err(format!("missing value for {}", arg))
+                };
+            },
+            "-c" | "--count" => count = true,
+            "-o" | "--only-matching" => only_matching = true,
+            "--" => {
+                paths.extend(args.by_ref().map(PathBuf::from));
+            },
+            _ if arg.starts_with('-') && arg.len() > 1 => return Err(format!("unknown option '{}'", arg)),
+            _ if pattern.is_none() => pattern = Some(arg),
+            _ => paths.push(PathBuf::from(arg))
+        }
+    }
+
+    // After "--" the first argument may still be the pattern.
+    let pattern = match pattern {
+        Some(pattern) => pattern,
+        None if !paths.is_empty() => paths.remove(0).to_string_lossy().into_owned(),
+        None => return Err(String::from("missing pattern"))
+    };
+
+    Ok(FindOptions { pattern, paths, algorithm, count, only_matching })
+}
+
+// Arguments after "--" are the pattern and paths, even if they look like options.
+fn wants_help(args: &[String]) -> bool {
+    args.iter().take_while(|arg| *arg != "--").any(|arg| arg == "-h" || arg == "--help")
+}
+
+struct Searcher<'a, M: Matcher, W: Write> {
+    matcher: &'a M,
+    options: &'a FindOptions,
+    output: W,
+    found: bool,
+    failed: bool
+}
+
+impl<'a, M: Matcher, W: Write> Searcher<'a, M, W> {
+    // Like grep, a failing path is reported and skipped; only output errors abort the search.
+    // Symbolic links are followed when given as arguments, but not inside of directories,
+    // so a link cycle cannot make the recursion endless.
+    fn search_path(&mut self, path: &Path) -> io::Result<()> {
+        if path.is_dir() {
+            self.search_directory(path)
+        }
+        else {
+            self.search_file(path)
+        }
+    }
+
+    fn search_directory(&mut self, path: &Path) -> io::Result<()> {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.report(Some(path), &error);
+                return Ok(());
+            }
+        };
+
+        let mut children = Vec::new();
+        for entry in entries {
+            match entry.and_then(|entry| Ok((entry.path(), entry.file_type()?))) {
+                Ok(child) => children.push(child),
+                Err(error) => self.report(Some(path), &error)
+            }
+        }
+        children.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        for (child, file_type) in children {
+            if file_type.is_dir() {
+                self.search_directory(&child)?;
+            }
+            else if !file_type.is_symlink() {
+                self.search_file(&child)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn search_file(&mut self, path: &Path) -> io::Result<()> {
+        match File::open(path) {
+            Ok(file) => self.search_reader(BufReader::new(file), Some(path)),
+            Err(error) => {
+                self.report(Some(path), &error);
+                Ok(())
+            }
+        }
+    }
+
+    fn report(&mut self, path: Option<&Path>, error: &io::Error) {
+        match path {
+            Some(path) => eprintln!("cormen: {}: {}", path.display(), error),
+            None => eprintln!("cormen: (standard input): {}", error)
+        }
+        self.failed = true;
+    }
+
+    fn search_reader<R: BufRead>(&mut self, mut reader: R, path: Option<&Path>) -> io::Result<()> {
+        let prefix = match path {
+            Some(path) => format!("{}:", path.display()),
+            None => String::new()
+        };
+
+        let mut buffer = Vec::new();
+        let mut line_number = 0;
+        let mut count = 0;
+
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => break,
+                Ok(_) => {},
+                Err(error) => {
+                    self.report(path, &error);
+                    break;
+                }
+            }
+            line_number += 1;
+
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            for span in self.matcher.find_iter(line) {
+                count += 1;
+                if self.options.count {
+                    continue;
+                }
+
+                let shown = if self.options.only_matching { self.options.pattern.as_str() } else { line };
+                writeln!(self.output, "{}{}:{}: {}", prefix, line_number, span.start + 1, shown)?;
+            }
+        }
+
+        if self.options.count {
+            writeln!(self.output, "{}{}", prefix, count)?;
+        }
+
+        self.found |= count > 0;
+        Ok(())
+    }
+}
+
+fn run_find<M: Matcher>(matcher: M, options: &FindOptions) -> io::Result<ExitCode> {
+    let stdout = io::stdout();
+    let mut searcher = Searcher { matcher: &matcher, options, output: stdout.lock(), found: false, failed: false };
+
+    if options.paths.is_empty() {
+        searcher.search_reader(io::stdin().lock(), None)?;
+    }
+
+    for path in &options.paths {
+        if path.as_os_str() == "-" {
+            searcher.search_reader(io::stdin().lock(), None)?;
+        }
+        else {
+            searcher.search_path(path)?;
+        }
+    }
+
+    searcher.output.flush()?;
+
+    // Exit codes follow grep: 0 if anything was found, 1 if not, 2 on errors.
+    Ok(match (searcher.found, searcher.failed) {
+        (_, true) => ExitCode::from(2),
+        (true, false) => ExitCode::SUCCESS,
+        (false, false) => ExitCode::from(1)
+    })
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+
+    match args.next().as_deref() {
+        Some("find") => {},
+        None | Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Some(other) => {
+            eprintln!("cormen: unknown command '{}'\n\n{}", other, USAGE);
+            return ExitCode::from(2);
+        }
+    }
+
+    let args: Vec<String> = args.collect();
+    if wants_help(&args) {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_find_options(args.into_iter()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("cormen: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match options.algorithm {
+        Algorithm::Naive => run_find(NaiveMatcher::compile(&options.pattern), &options),
+        Algorithm::Kmp => run_find(KmpMatcher::compile(&options.pattern), &options)
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("cormen: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> impl Iterator<Item = String> {
+        values.iter().map(|value| value.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    fn parse_find_options_test() {
+        let options = parse_find_options(args(&["-a", "naive", "--count", "abc", "src", "README"])).unwrap();
+        assert_eq!(FindOptions {
+            pattern: String::from("abc"),
+            paths: vec![PathBuf::from("src"), PathBuf::from("README")],
+            algorithm: Algorithm::Naive,
+            count: true,
+            only_matching: false
+        }, options);
+
+        let options = parse_find_options(args(&["-o", "--", "-x"])).unwrap();
+        assert_eq!("-x", options.pattern);
+        assert!(options.only_matching);
+        assert_eq!(Algorithm::Kmp, options.algorithm);
+
+        assert!(parse_find_options(args(&[])).is_err());
+        assert!(parse_find_options(args(&["-a", "boyer-moore", "x"])).is_err());
+        assert!(parse_find_options(args(&["--color", "x"])).is_err());
+    }
+
+    #[test]
+    fn wants_help_test() {
+        assert!(wants_help(&args(&["abc", "--help"]).collect::<Vec<_>>()));
+        assert!(wants_help(&args(&["-h", "--", "abc"]).collect::<Vec<_>>()));
+        assert!(!wants_help(&args(&["--", "-h", "src"]).collect::<Vec<_>>()));
+        assert!(!wants_help(&args(&["abc", "--", "--help"]).collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn search_path_test() {
+        let root = env::temp_dir().join(format!("cormen-search-path-{}", std::process::id()));
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), "xab\n").unwrap();
+        fs::write(root.join("sub").join("b.txt"), "no\nab\n").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("sub").join("loop")).unwrap();
+
+        let matcher = KmpMatcher::compile("ab");
+        let options = FindOptions { pattern: String::from("ab"), paths: Vec::new(), algorithm: Algorithm::Kmp, count: false, only_matching: true };
+        let mut searcher = Searcher { matcher: &matcher, options: &options, output: Vec::new(), found: false, failed: false };
+        searcher.search_path(&root.join("missing")).unwrap();
+        searcher.search_path(&root).unwrap();
+        let output = String::from_utf8(searcher.output).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(searcher.found);
+        assert!(searcher.failed);
+        let expected = format!("{}:1:2: ab\n{}:2:1: ab\n", root.join("a.txt").display(), root.join("sub").join("b.txt").display());
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn search_reader_test() {
+        check_search_reader(NaiveMatcher::compile("ab"), Algorithm::Naive);
+        check_search_reader(KmpMatcher::compile("ab"), Algorithm::Kmp);
+    }
+
+    fn check_search_reader<M: Matcher>(matcher: M, algorithm: Algorithm) {
+        let text = "abab\nżab\n\nno\r\n";
+
+        let options = FindOptions { pattern: String::from("ab"), paths: Vec::new(), algorithm, count: false, only_matching: false };
+        let mut searcher = Searcher { matcher: &matcher, options: &options, output: Vec::new(), found: false, failed: false };
+        searcher.search_reader(text.as_bytes(), Some(Path::new("a.txt"))).unwrap();
+        assert!(searcher.found);
+        assert_eq!("a.txt:1:1: abab\na.txt:1:3: abab\na.txt:2:2: żab\n", String::from_utf8(searcher.output).unwrap());
+
+        let options = FindOptions { count: true, ..options };
+        let mut searcher = Searcher { matcher: &matcher, options: &options, output: Vec::new(), found: false, failed: false };
+        searcher.search_reader(text.as_bytes(), None).unwrap();
+        assert_eq!("3\n", String::from_utf8(searcher.output).unwrap());
+
+        let options = FindOptions { count: false, only_matching: true, ..options };
+        let mut searcher = Searcher { matcher: &matcher, options: &options, output: Vec::new(), found: false, failed: false };
+        searcher.search_reader("xab".as_bytes(), None).unwrap();
+        assert_eq!("1:2: ab\n", String::from_utf8(searcher.output).unwrap());
+
+        let mut searcher = Searcher { matcher: &matcher, options: &options, output: Vec::new(), found: false, failed: false };
+        searcher.search_reader("xyz".as_bytes(), None).unwrap();
+        assert!(!searcher.found);
+    }
 }
@@ -1,70 +1,286 @@
-/// Finds first occurrence of a pattern in a text using naive algorithm.
-pub fn naive_find(pattern: &String, text: &String) -> Vec<usize> {
-    let mut result = Vec::new();
-    let pattern_vec: Vec<char> = pattern.chars().collect();
-    if pattern_vec.len() == 0 {
-        return result;
+pub mod approximate;
+pub mod fm_index;
+pub mod glob;
+pub mod regex;
+pub mod replace;
+pub mod unicode;
+
+use std::io::{self, Read};
+use std::ops::Range;
+use std::str::{CharIndices, Chars};
+
+/// A pattern compiled once and matched against many texts.
+/// Spans returned by the matchers are ranges of character (not byte) positions in the text.
+pub trait Matcher {
+    type Matches<'a>: Iterator<Item = Range<usize>> where Self: 'a;
+
+    /// Returns an iterator over the spans of all (possibly overlapping) matches, ordered by
+    /// their starts. Matches of patterns like regular expressions may differ in length.
+    fn find_iter<'a>(&'a self, text: &'a str) -> Self::Matches<'a>;
+
+    /// Finds the first occurrence of the pattern, stopping as soon as it is found.
+    fn find_first(&self, text: &str) -> Option<Range<usize>> {
+        self.find_iter(text).next()
     }
 
-    let text_vec: Vec<char> = text.chars().collect();
-    
-    for i in 0..text_vec.len() - pattern_vec.len() + 1 {
-        for j in 0..pattern_vec.len() {
-            if pattern_vec[j] != text_vec[i + j] {
-                break;
+    /// Counts all (possibly overlapping) occurrences of the pattern.
+    fn count(&self, text: &str) -> usize {
+        self.find_iter(text).count()
+    }
+
+    /// Checks if the pattern occurs anywhere in the text.
+    fn is_match(&self, text: &str) -> bool {
+        self.find_first(text).is_some()
+    }
+}
+
+/// Matcher comparing the pattern with every position of the text.
+pub struct NaiveMatcher {
+    pattern: Vec<char>
+}
+
+pub struct NaiveMatches<'a> {
+    pattern: &'a [char],
+    text: &'a str,
+    byte_offsets: CharIndices<'a>,
+    position: usize
+}
+
+impl NaiveMatcher {
+    pub fn compile(pattern: &str) -> Self {
+        NaiveMatcher { pattern: pattern.chars().collect() }
+    }
+
+    /// Length of the pattern in characters.
+    pub fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
+}
+
+impl Matcher for NaiveMatcher {
+    type Matches<'a> = NaiveMatches<'a>;
+
+    fn find_iter<'a>(&'a self, text: &'a str) -> NaiveMatches<'a> {
+        NaiveMatches { pattern: &self.pattern, text, byte_offsets: text.char_indices(), position: 0 }
+    }
+}
+
+impl<'a> Iterator for NaiveMatches<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+
+        for (byte_offset, _) in self.byte_offsets.by_ref() {
+            let i = self.position;
+            self.position += 1;
+
+            let mut text_chars = self.text[byte_offset..].chars();
+            let mut matched = 0;
+
+            for pattern_char in self.pattern {
+                match text_chars.next() {
+                    Some(text_char) if text_char == *pattern_char => matched += 1,
+                    Some(_) => break,
+                    // The rest of the text is shorter than the pattern.
+                    None => return None
+                }
             }
-            else if j == pattern_vec.len() - 1 {
-                result.push(i);
+
+            if matched == self.pattern.len() {
+                return Some(i..i + matched);
             }
         }
+
+        None
+    }
+}
+
+/// Matcher using Knuth-Morris-Pratt algorithm. The prefix table is built once in `compile`.
+/// Patterns of other elements than characters, like bytes, are built with `from_slice`.
+pub struct KmpMatcher<T = char> {
+    pattern: Vec<T>,
+    prefix_table: Vec<usize>
+}
+
+pub struct KmpMatches<'a> {
+    matcher: &'a KmpMatcher,
+    text: Chars<'a>,
+    position: usize,
+    longest_prefix: usize
+}
+
+impl KmpMatcher<char> {
+    pub fn compile(pattern: &str) -> Self {
+        KmpMatcher::from_slice(&pattern.chars().collect::<Vec<_>>())
     }
-    
-    result
 }
 
-/// Finds first occurrence of a pattern in a text using Knuth-Morris-Pratt algorithm.
+impl<T: PartialEq + Clone> KmpMatcher<T> {
+    pub fn from_slice(pattern: &[T]) -> Self {
+        KmpMatcher { pattern: pattern.to_vec(), prefix_table: create_kmp_prefix_table(pattern) }
+    }
+
+    /// Length of the pattern in elements (characters for `compile`).
+    pub fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    // One transition of the automaton; the state is the length of the longest prefix of the
+    // pattern ending at the current element. Returns true when a whole match ends here.
+    // The pattern must not be empty.
+    fn advance(&self, state: &mut usize, value: &T) -> bool {
+        while *state > 0 && *value != self.pattern[*state] {
+            *state = self.prefix_table[*state - 1];
+        }
+
+        if *value == self.pattern[*state] {
+            *state += 1;
+        }
+
+        if *state == self.pattern.len() {
+            *state = self.prefix_table[*state - 1];
+            return true;
+        }
+
+        false
+    }
+}
+
+impl Matcher for KmpMatcher {
+    type Matches<'a> = KmpMatches<'a>;
+
+    fn find_iter<'a>(&'a self, text: &'a str) -> KmpMatches<'a> {
+        KmpMatches { matcher: self, text: text.chars(), position: 0, longest_prefix: 0 }
+    }
+}
+
+impl<'a> Iterator for KmpMatches<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let length = self.matcher.pattern_len();
+        if length == 0 {
+            return None;
+        }
+
+        for text_char in self.text.by_ref() {
+            self.position += 1;
+
+            if self.matcher.advance(&mut self.longest_prefix, &text_char) {
+                return Some(self.position - length..self.position);
+            }
+        }
+
+        None
+    }
+}
+
+/// Finds all occurrences of a pattern in a text using naive algorithm.
+pub fn naive_find(pattern: &String, text: &String) -> Vec<usize> {
+    NaiveMatcher::compile(pattern).find_iter(text).map(|span| span.start).collect()
+}
+
+/// Finds all occurrences of a pattern in a text using Knuth-Morris-Pratt algorithm.
 pub fn kmp_find(pattern: &String, text: &String) -> Vec<usize> {
-    let pattern_vec: Vec<char> = pattern.chars().collect();
-    let mut result = Vec::new();
+    KmpMatcher::compile(pattern).find_iter(text).map(|span| span.start).collect()
+}
 
-    if pattern_vec.len() == 0 {
-        return result;
+/// Resumable Knuth-Morris-Pratt state machine, which accepts the text in arbitrary chunks.
+/// Matches spanning chunk boundaries are found as well.
+///
+/// Unlike the `Matcher`s, which report character offsets, it works on bytes: the reported
+/// offsets are absolute byte positions in the whole stream, and a chunk may end in the middle
+/// of a UTF-8 character.
+pub struct StreamSearcher {
+    matcher: KmpMatcher<u8>,
+    longest_prefix: usize,
+    position: usize
+}
+
+impl StreamSearcher {
+    const BUFFER_SIZE: usize = 64 * 1024;
+
+    pub fn new(pattern: &[u8]) -> Self {
+        StreamSearcher {
+            matcher: KmpMatcher::from_slice(pattern),
+            longest_prefix: 0,
+            position: 0
+        }
     }
 
-    let prefix_table  = create_kmp_prefix_table(&pattern_vec);
-    let mut longest_prefix = 0;
-    let mut i = 0;
-    
-    for text_char in text.chars() {
-        i += 1;
+    /// Number of bytes consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
 
-        while longest_prefix > 0 && text_char != pattern_vec[longest_prefix] {
-            longest_prefix = prefix_table[longest_prefix];
+    /// Forgets the consumed input, so that a new stream can be searched.
+    pub fn reset(&mut self) {
+        self.longest_prefix = 0;
+        self.position = 0;
+    }
+
+    /// Consumes the next chunk and calls `on_match` with the start byte offset of every match
+    /// that ends inside of it.
+    pub fn feed_with<F: FnMut(usize)>(&mut self, chunk: &[u8], mut on_match: F) {
+        let length = self.matcher.pattern_len();
+        if length == 0 {
+            self.position += chunk.len();
+            return;
         }
-        
-        if text_char == pattern_vec[longest_prefix] {
-            longest_prefix += 1;
+
+        for byte in chunk {
+            self.position += 1;
+
+            if self.matcher.advance(&mut self.longest_prefix, byte) {
+                on_match(self.position - length);
+            }
         }
-        
-        if longest_prefix == pattern_vec.len() {
-            result.push(i - pattern_vec.len());
-            longest_prefix = prefix_table[longest_prefix - 1];
+    }
+
+    /// Consumes the next chunk and returns start byte offsets of matches that end inside of it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<usize> {
+        let mut result = Vec::new();
+        self.feed_with(chunk, |offset| result.push(offset));
+        result
+    }
+
+    /// Reads the whole input in fixed-size buffers and returns byte offsets of all matches.
+    pub fn feed_reader<R: Read>(&mut self, mut reader: R) -> io::Result<Vec<usize>> {
+        let mut buffer = vec![0; Self::BUFFER_SIZE];
+        let mut result = Vec::new();
+
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error)
+            };
+
+            self.feed_with(&buffer[..read], |offset| result.push(offset));
         }
+
+        Ok(result)
     }
-    
-    result
 }
 
-// Prefix table: a mapping of a longest suffix of a sub-string 
-// that is also its prefix.
-fn create_kmp_prefix_table(pattern: &Vec<char>) -> Vec<usize> {
+/// Prefix function (the KMP prefix table): for every position `i`, the length of the longest
+/// proper suffix of `pattern[..=i]` that is also its prefix.
+pub fn create_kmp_prefix_table<T: PartialEq>(pattern: &[T]) -> Vec<usize> {
     let mut result = Vec::new();
+    if pattern.is_empty() {
+        return result;
+    }
+
     result.push(0);
     
     for i in 1..pattern.len() {
         let mut j = result[i - 1];
         while j > 0 && pattern[j] != pattern[i] {
-            j = result[j];
+            j = result[j - 1];
         }
         
         if pattern[j] == pattern[i] {
@@ -77,9 +293,171 @@ fn create_kmp_prefix_table(pattern: &Vec<char>) -> Vec<usize> {
     result
 }
 
+/// Z-array: for every position `i`, the length of the longest common prefix of `text` and
+/// `text[i..]`. By convention `z[0]` is the length of the whole text.
+pub fn z_array<T: PartialEq>(text: &[T]) -> Vec<usize> {
+    let n = text.len();
+    let mut z = vec![0; n];
+    if n == 0 {
+        return z;
+    }
+
+    z[0] = n;
+    // [left, right) is the rightmost segment known to match a prefix of the text.
+    let mut left = 0;
+    let mut right = 0;
+
+    for i in 1..n {
+        if i < right {
+            z[i] = (right - i).min(z[i - left]);
+        }
+
+        while i + z[i] < n && text[z[i]] == text[i + z[i]] {
+            z[i] += 1;
+        }
+
+        if i + z[i] > right {
+            left = i;
+            right = i + z[i];
+        }
+    }
+
+    z
+}
+
+/// Converts a Z-array into the corresponding prefix function in linear time.
+pub fn z_to_prefix_function(z: &[usize]) -> Vec<usize> {
+    let n = z.len();
+    let mut prefix = vec![0; n];
+
+    for i in 1..n {
+        // Longer matches starting earlier were already written, so we can stop at the first
+        // position that has a value.
+        for j in (0..z[i]).rev() {
+            if prefix[i + j] > 0 {
+                break;
+            }
+
+            prefix[i + j] = j + 1;
+        }
+    }
+
+    prefix
+}
+
+/// Converts a prefix function into the corresponding Z-array in linear time.
+pub fn prefix_function_to_z(prefix: &[usize]) -> Vec<usize> {
+    let n = prefix.len();
+    let mut z = vec![0; n];
+    if n == 0 {
+        return z;
+    }
+
+    for i in 1..n {
+        if prefix[i] > 0 {
+            z[i + 1 - prefix[i]] = prefix[i];
+        }
+    }
+
+    z[0] = n;
+    let mut i = 1;
+    while i < n {
+        let mut next = i;
+        if z[i] > 0 {
+            for j in 1..z[i] {
+                if z[i + j] > z[j] {
+                    break;
+                }
+
+                z[i + j] = z[j].min(z[i] - j);
+                next = i + j;
+            }
+        }
+
+        i = next + 1;
+    }
+
+    z
+}
+
+/// Lengths of all non-empty proper borders (substrings being both a prefix and a suffix),
+/// from the longest to the shortest.
+pub fn borders<T: PartialEq>(text: &[T]) -> Vec<usize> {
+    let prefix = create_kmp_prefix_table(text);
+    let mut result = Vec::new();
+
+    let mut border = prefix.last().copied().unwrap_or(0);
+    while border > 0 {
+        result.push(border);
+        border = prefix[border - 1];
+    }
+
+    result
+}
+
+/// All periods `p` of the text, i.e. `text[i] == text[i + p]` for every valid `i`, in increasing
+/// order. The length of the text is always its (trivial) period.
+pub fn periods<T: PartialEq>(text: &[T]) -> Vec<usize> {
+    let mut result: Vec<usize> = borders(text).into_iter().map(|border| text.len() - border).collect();
+    if !text.is_empty() {
+        result.push(text.len());
+    }
+
+    result
+}
+
+/// The smallest period of the text, or 0 for an empty text.
+pub fn smallest_period<T: PartialEq>(text: &[T]) -> usize {
+    let prefix = create_kmp_prefix_table(text);
+    text.len() - prefix.last().copied().unwrap_or(0)
+}
+
+/// The shortest unit, which repeated a whole number of times gives the text.
+/// # Examples
+/// ```
+/// use cormen_rust::patterns::smallest_repeating_unit;
+/// assert_eq!(b"abc", smallest_repeating_unit(b"abcabcabc"));
+/// assert_eq!(b"abcab", smallest_repeating_unit(b"abcab"));
+/// ```
+pub fn smallest_repeating_unit<T: PartialEq>(text: &[T]) -> &[T] {
+    let period = smallest_period(text);
+    if period > 0 && text.len().is_multiple_of(period) {
+        &text[..period]
+    }
+    else {
+        text
+    }
+}
+
+/// Counts occurrences of every prefix in the text: `result[k]` is the number of occurrences
+/// of the prefix of length `k`. The empty prefix occurs `text.len() + 1` times.
+pub fn prefix_occurrences<T: PartialEq>(text: &[T]) -> Vec<usize> {
+    let n = text.len();
+    let prefix = create_kmp_prefix_table(text);
+    let mut result = vec![0; n + 1];
+
+    for &border in &prefix {
+        result[border] += 1;
+    }
+
+    // Every occurrence of a prefix is also an occurrence of its longest border.
+    for length in (1..=n).rev() {
+        result[prefix[length - 1]] += result[length];
+    }
+
+    for count in result.iter_mut().skip(1) {
+        *count += 1;
+    }
+    result[0] = n + 1;
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
 
     #[test]
     fn prefix_table_two_characters() {
@@ -96,6 +474,14 @@ mod tests {
 
         assert_eq!(vec![0, 1, 2, 3, 4], prefix_table);
     }
+
+    #[test]
+    fn prefix_table_fallback() {
+        let pattern = vec!['a', 'a', 'b', 'a', 'a', 'a'];
+        let prefix_table = create_kmp_prefix_table(&pattern);
+
+        assert_eq!(vec![0, 1, 0, 1, 2, 2], prefix_table);
+    }
     
     #[test]
     fn naive_find_test() {
@@ -113,4 +499,99 @@ mod tests {
         assert_eq!(finder(&String::from("bab"), &String::from("aababab")), vec![2, 4]);
         assert_eq!(finder(&String::from("abc"), &String::from("bdbdbdabd")), Vec::new());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn naive_matcher_test() {
+        run_matcher_tests(NaiveMatcher::compile);
+    }
+
+    #[test]
+    fn kmp_matcher_test() {
+        run_matcher_tests(KmpMatcher::compile);
+    }
+
+    fn run_matcher_tests<M: Matcher>(compile: fn(&str) -> M) {
+        let matcher = compile("aab");
+        assert_eq!(matcher.find_iter("aaabaab").collect::<Vec<_>>(), vec![1..4, 4..7]);
+        assert_eq!(matcher.find_iter("xaab").collect::<Vec<_>>(), vec![1..4]);
+        assert_eq!(matcher.find_first("aaabaab"), Some(1..4));
+        assert_eq!(matcher.find_first("ab"), None);
+        assert_eq!(matcher.count("aabaabaab"), 3);
+        assert!(matcher.is_match("zzaabzz"));
+        assert!(!matcher.is_match("abab"));
+
+        let matcher = compile("żó");
+        assert_eq!(matcher.find_iter("żółżó").collect::<Vec<_>>(), vec![0..2, 3..5]);
+
+        let matcher = compile("");
+        assert_eq!(matcher.find_first("abc"), None);
+    }
+
+    #[test]
+    fn stream_searcher_chunks_test() {
+        let mut searcher = StreamSearcher::new(b"abaab");
+        let text = b"abbabcabaabcdabaabaab";
+        let expected = vec![6, 13, 16];
+
+        for chunk_size in 1..=text.len() {
+            searcher.reset();
+            let mut result = Vec::new();
+            for chunk in text.chunks(chunk_size) {
+                result.extend(searcher.feed(chunk));
+            }
+
+            assert_eq!(expected, result);
+            assert_eq!(text.len(), searcher.position());
+        }
+    }
+
+    #[test]
+    fn stream_searcher_reader_test() {
+        let text = "żółw ".repeat(20000);
+        let mut searcher = StreamSearcher::new("w ż".as_bytes());
+
+        // Offsets are in bytes: "żół" takes 6 of them, but only 3 characters.
+        let result = searcher.feed_reader(text.as_bytes()).unwrap();
+        assert_eq!(19999, result.len());
+        assert_eq!(6, result[0]);
+        assert_eq!(text.len() - 10, *result.last().unwrap());
+    }
+
+    #[test]
+    fn z_array_test() {
+        assert_eq!(vec![7, 0, 1, 0, 3, 0, 1], z_array(b"abacaba"));
+        assert_eq!(vec![5, 4, 3, 2, 1], z_array(b"aaaaa"));
+        assert_eq!(Vec::<usize>::new(), z_array::<u8>(&[]));
+    }
+
+    #[test]
+    fn z_prefix_conversion_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(28);
+
+        for _ in 0..500 {
+            let length = rng.gen_range(1..40);
+            let text: Vec<u8> = (0..length).map(|_| rng.gen_range(b'a'..b'd')).collect();
+            let z = z_array(&text);
+            let prefix = create_kmp_prefix_table(&text);
+
+            assert_eq!(prefix, z_to_prefix_function(&z));
+            assert_eq!(z, prefix_function_to_z(&prefix));
+        }
+    }
+
+    #[test]
+    fn borders_and_periods_test() {
+        assert_eq!(vec![3, 1], borders(b"abacaba"));
+        assert_eq!(vec![4, 6, 7], periods(b"abacaba"));
+        assert_eq!(4, smallest_period(b"abacaba"));
+        assert_eq!(Vec::<usize>::new(), borders(b"abc"));
+        assert_eq!(&[1, 2], smallest_repeating_unit(&[1, 2, 1, 2, 1, 2]));
+    }
+
+    #[test]
+    fn prefix_occurrences_test() {
+        // Prefixes of "abab": "a" x2, "ab" x2, "aba" x1, "abab" x1.
+        assert_eq!(vec![5, 2, 2, 1, 1], prefix_occurrences(b"abab"));
+        assert_eq!(vec![4, 3, 2, 1], prefix_occurrences(b"aaa"));
+    }
+}
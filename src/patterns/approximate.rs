@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::str::Chars;
+
+/// A substring of the text matching the pattern with at most `k` errors. `end` is the
+/// (exclusive) character offset where the substring ends, `errors` is the smallest number
+/// of errors among substrings ending there.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ApproximateMatch {
+    pub end: usize,
+    pub errors: usize
+}
+
+/// Kind of errors allowed by the approximate matchers.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorModel {
+    /// Only substitutions, i.e. the Hamming distance.
+    Substitutions,
+    /// Substitutions, insertions and deletions, i.e. the Levenshtein distance.
+    Edits
+}
+
+/// Bitap (shift-and) matcher with Wu-Manber extension for errors. Keeps `k + 1` bit masks,
+/// so it runs in O(nk) for patterns of up to 64 characters.
+pub struct BitapMatcher {
+    pattern_length: usize,
+    masks: HashMap<char, u64>,
+    max_errors: usize,
+    model: ErrorModel
+}
+
+pub struct BitapMatches<'a> {
+    matcher: &'a BitapMatcher,
+    text: Chars<'a>,
+    position: usize,
+    // states[d] has bit i set if the pattern prefix of length i + 1 matches with at most d errors.
+    states: Vec<u64>
+}
+
+impl BitapMatcher {
+    pub const MAX_PATTERN_LENGTH: usize = 64;
+
+    /// Returns `None` if the pattern is longer than `MAX_PATTERN_LENGTH` characters;
+    /// `sellers_find` handles patterns of any length.
+    pub fn new(pattern: &str, max_errors: usize, model: ErrorModel) -> Option<Self> {
+        let mut masks = HashMap::new();
+        let mut pattern_length = 0;
+
+        for (i, c) in pattern.chars().enumerate() {
+            if i == Self::MAX_PATTERN_LENGTH {
+                return None;
+            }
+            *masks.entry(c).or_insert(0) |= 1 << i;
+            pattern_length += 1;
+        }
+
+        Some(BitapMatcher { pattern_length, masks, max_errors, model })
+    }
+
+    /// Returns a lazy iterator over all text positions where a match with at most `max_errors` ends.
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> BitapMatches<'a> {
+        // With edits, the first d characters of the pattern can be deleted up front.
+        let states = (0..=self.max_errors)
+            .map(|errors| match self.model {
+                ErrorModel::Substitutions => 0,
+                ErrorModel::Edits => low_bits(errors.min(self.pattern_length))
+            })
+            .collect();
+
+        BitapMatches { matcher: self, text: text.chars(), position: 0, states }
+    }
+}
+
+impl<'a> Iterator for BitapMatches<'a> {
+    type Item = ApproximateMatch;
+
+    fn next(&mut self) -> Option<ApproximateMatch> {
+        let matcher = self.matcher;
+        if matcher.pattern_length == 0 {
+            return None;
+        }
+
+        let accept = 1 << (matcher.pattern_length - 1);
+
+        for c in self.text.by_ref() {
+            self.position += 1;
+            let mask = matcher.masks.get(&c).copied().unwrap_or(0);
+
+            let mut previous_old = self.states[0];
+            self.states[0] = ((self.states[0] << 1) | 1) & mask;
+
+            for errors in 1..self.states.len() {
+                let old = self.states[errors];
+                let mut state = ((old << 1) | 1) & mask
+                    | ((previous_old << 1) | 1);
+
+                if matcher.model == ErrorModel::Edits {
+                    state |= ((self.states[errors - 1] << 1) | 1) | previous_old;
+                }
+
+                self.states[errors] = state;
+                previous_old = old;
+            }
+
+            if let Some(errors) = self.states.iter().position(|state| state & accept != 0) {
+                return Some(ApproximateMatch { end: self.position, errors });
+            }
+        }
+
+        None
+    }
+}
+
+/// Sellers' algorithm: dynamic programming over edit distances, where a match may start at
+/// any position of the text. Runs in O(nm) time and O(m) space. Unlike `BitapMatcher`, it
+/// has no limit on the pattern length.
+pub fn sellers_find(pattern: &str, text: &str, max_errors: usize, model: ErrorModel) -> Vec<ApproximateMatch> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut result = Vec::new();
+    if pattern.is_empty() {
+        return result;
+    }
+
+    // column[i] is the distance between the pattern prefix of length i and the best
+    // substring ending at the current position. With substitutions only, the substring must
+    // be as long as the prefix, so before the text starts it cannot match at all.
+    let mut column: Vec<usize> = (0..=pattern.len())
+        .map(|i| if i > 0 && model == ErrorModel::Substitutions { usize::MAX } else { i })
+        .collect();
+
+    for (position, c) in text.chars().enumerate() {
+        let mut diagonal = column[0];
+        for i in 1..=pattern.len() {
+            let substitution = diagonal.saturating_add(usize::from(pattern[i - 1] != c));
+            diagonal = column[i];
+            column[i] = match model {
+                ErrorModel::Substitutions => substitution,
+                ErrorModel::Edits => substitution.min(column[i] + 1).min(column[i - 1] + 1)
+            };
+        }
+
+        if column[pattern.len()] <= max_errors {
+            result.push(ApproximateMatch { end: position + 1, errors: column[pattern.len()] });
+        }
+    }
+
+    result
+}
+
+#[inline]
+fn low_bits(count: usize) -> u64 {
+    if count >= 64 { u64::MAX } else { (1 << count) - 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn bitap_substitutions_test() {
+        let matcher = BitapMatcher::new("AB-1234", 1, ErrorModel::Substitutions).unwrap();
+        let matches: Vec<ApproximateMatch> = matcher.find_iter("code AB-1284 and AB-1234, not AB1234").collect();
+
+        assert_eq!(vec![
+            ApproximateMatch { end: 12, errors: 1 },
+            ApproximateMatch { end: 24, errors: 0 }
+        ], matches);
+    }
+
+    #[test]
+    fn bitap_edits_test() {
+        let matcher = BitapMatcher::new("AB-1234", 1, ErrorModel::Edits).unwrap();
+        let matches: Vec<ApproximateMatch> = matcher.find_iter("not AB1234").collect();
+
+        assert_eq!(vec![ApproximateMatch { end: 10, errors: 1 }], matches);
+    }
+
+    #[test]
+    fn sellers_test() {
+        assert_eq!(vec![
+            ApproximateMatch { end: 3, errors: 1 },
+            ApproximateMatch { end: 4, errors: 0 },
+            ApproximateMatch { end: 5, errors: 1 }
+        ], sellers_find("abc", "xabcx", 1, ErrorModel::Edits));
+        assert!(sellers_find("", "abc", 1, ErrorModel::Edits).is_empty());
+
+        assert_eq!(vec![ApproximateMatch { end: 4, errors: 0 }], sellers_find("abc", "xabcx", 1, ErrorModel::Substitutions));
+    }
+
+    #[test]
+    fn long_pattern_test() {
+        let pattern = "ab".repeat(40);
+        assert!(BitapMatcher::new(&pattern[..BitapMatcher::MAX_PATTERN_LENGTH], 2, ErrorModel::Edits).is_some());
+        assert!(BitapMatcher::new(&pattern, 2, ErrorModel::Edits).is_none());
+
+        let text = format!("x{}x", pattern.replacen("ab", "b", 1));
+        assert_eq!(vec![
+            ApproximateMatch { end: 79, errors: 2 },
+            ApproximateMatch { end: 80, errors: 1 },
+            ApproximateMatch { end: 81, errors: 2 }
+        ], sellers_find(&pattern, &text, 2, ErrorModel::Edits));
+    }
+
+    #[test]
+    fn bitap_equals_sellers_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(32);
+
+        for _ in 0..300 {
+            let text: String = (0..rng.gen_range(0..50)).map(|_| rng.gen_range('a'..'d')).collect();
+            let pattern: String = (0..rng.gen_range(1..8)).map(|_| rng.gen_range('a'..'d')).collect();
+            let max_errors = rng.gen_range(0..4);
+
+            for model in [ErrorModel::Substitutions, ErrorModel::Edits] {
+                let matcher = BitapMatcher::new(&pattern, max_errors, model).unwrap();
+                let bitap: Vec<ApproximateMatch> = matcher.find_iter(&text).collect();
+                assert_eq!(sellers_find(&pattern, &text, max_errors, model), bitap);
+            }
+        }
+    }
+}
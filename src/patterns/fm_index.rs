@@ -0,0 +1,350 @@
+use crate::suffix_array::build_suffix_array;
+
+/// Burrows-Wheeler transform of a byte text terminated with an implicit sentinel, which is
+/// smaller than any byte. The last column has `text.len() + 1` entries; the entry at
+/// `sentinel` stands for the sentinel itself and holds 0.
+#[derive(Debug, PartialEq)]
+pub struct Bwt {
+    pub last_column: Vec<u8>,
+    pub sentinel: usize
+}
+
+pub fn bwt(text: &[u8]) -> Bwt {
+    bwt_from_suffixes(text, &build_suffix_array(text))
+}
+
+/// Reconstructs the original text using the last-to-first mapping. An empty last column
+/// (not even the sentinel) gives an empty text.
+pub fn inverse_bwt(transform: &Bwt) -> Vec<u8> {
+    let last_column = &transform.last_column;
+    if last_column.is_empty() {
+        return Vec::new();
+    }
+    let n = last_column.len() - 1;
+    let counts = count_smaller(last_column, transform.sentinel);
+
+    // The rank of every entry among equal bytes above it.
+    let mut seen = [0; 256];
+    let mut ranks = vec![0; last_column.len()];
+    for (row, &byte) in last_column.iter().enumerate() {
+        if row != transform.sentinel {
+            ranks[row] = seen[byte as usize];
+            seen[byte as usize] += 1;
+        }
+    }
+
+    let mut result = vec![0; n];
+    // Row 0 starts with the sentinel, so its last entry is the last byte of the text.
+    let mut row = 0;
+    for position in (0..n).rev() {
+        let byte = last_column[row];
+        result[position] = byte;
+        row = counts[byte as usize] + ranks[row];
+    }
+
+    result
+}
+
+/// FM-index: a compressed full-text index answering `count` and `locate` queries with backward
+/// search over the BWT. Memory use is controlled by two sampling rates: occurrence counts are
+/// stored every `occ_sample_rate` rows and suffix array entries for every `sa_sample_rate`-th
+/// text position. Larger rates save memory at the cost of slower queries.
+///
+/// The sampling only shrinks the finished index: the BWT itself is kept uncompressed, one byte
+/// per row, next to about `n / occ_sample_rate * σ + n / sa_sample_rate + n / 32` words of
+/// samples and rank directory (σ is the number of distinct bytes). Construction goes through
+/// the full suffix array, built by prefix doubling with five words per text byte, so it peaks
+/// at about 40n bytes on 64-bit targets on top of the text.
+pub struct FmIndex {
+    last_column: Vec<u8>,
+    sentinel: usize,
+    // Number of text bytes smaller than the given one, plus one for the sentinel.
+    counts: [usize; 256],
+    // Dense identifiers of bytes present in the text, `None` for absent ones.
+    alphabet: [Option<usize>; 256],
+    alphabet_size: usize,
+    occ_sample_rate: usize,
+    occurrences: Vec<usize>,
+    sa_sample_rate: usize,
+    sampled_rows: RankBitVector,
+    sa_samples: Vec<usize>
+}
+
+impl FmIndex {
+    pub const DEFAULT_OCC_SAMPLE_RATE: usize = 64;
+    pub const DEFAULT_SA_SAMPLE_RATE: usize = 32;
+
+    pub fn new(text: &[u8]) -> Self {
+        FmIndex::with_sampling(text, Self::DEFAULT_OCC_SAMPLE_RATE, Self::DEFAULT_SA_SAMPLE_RATE)
+    }
+
+    /// Builds the index with custom sampling rates (both must be positive). Takes O(n log n)
+    /// time and, temporarily, the memory of a suffix array construction (see `FmIndex`).
+    pub fn with_sampling(text: &[u8], occ_sample_rate: usize, sa_sample_rate: usize) -> Self {
+        assert!(occ_sample_rate > 0 && sa_sample_rate > 0, "Sampling rates must be positive");
+
+        // Only the suffix order is needed, and only until the samples are taken.
+        let suffixes = build_suffix_array(text);
+        let Bwt { last_column, sentinel } = bwt_from_suffixes(text, &suffixes);
+
+        // Row 0 is the suffix starting at position n, so the rows are shifted by one.
+        let positions = std::iter::once(text.len()).chain(suffixes.iter().copied());
+        let mut sampled_rows = RankBitVector::new(last_column.len());
+        let mut sa_samples = Vec::new();
+        for (row, position) in positions.enumerate() {
+            if position % sa_sample_rate == 0 {
+                sampled_rows.set(row);
+                sa_samples.push(position);
+            }
+        }
+        sampled_rows.build_ranks();
+        drop(suffixes);
+
+        let counts = count_smaller(&last_column, sentinel);
+
+        let mut alphabet = [None; 256];
+        let mut alphabet_size = 0;
+        for &byte in text {
+            if alphabet[byte as usize].is_none() {
+                alphabet[byte as usize] = Some(alphabet_size);
+                alphabet_size += 1;
+            }
+        }
+
+        let mut occurrences = Vec::with_capacity((last_column.len() / occ_sample_rate + 1) * alphabet_size);
+        let mut running = vec![0; alphabet_size];
+        for (row, &byte) in last_column.iter().enumerate() {
+            if row % occ_sample_rate == 0 {
+                occurrences.extend_from_slice(&running);
+            }
+            if row != sentinel {
+                running[alphabet[byte as usize].unwrap()] += 1;
+            }
+        }
+        if last_column.len() % occ_sample_rate == 0 {
+            occurrences.extend_from_slice(&running);
+        }
+
+        FmIndex {
+            last_column,
+            sentinel,
+            counts,
+            alphabet,
+            alphabet_size,
+            occ_sample_rate,
+            occurrences,
+            sa_sample_rate,
+            sampled_rows,
+            sa_samples
+        }
+    }
+
+    /// Length of the indexed text.
+    pub fn len(&self) -> usize {
+        self.last_column.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn sa_sample_rate(&self) -> usize {
+        self.sa_sample_rate
+    }
+
+    /// Counts (possibly overlapping) occurrences of a non-empty pattern.
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        match self.backward_search(pattern) {
+            Some((begin, end)) => end - begin,
+            None => 0
+        }
+    }
+
+    /// Finds positions of all occurrences of a non-empty pattern. The result is sorted.
+    pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        let mut result: Vec<usize> = match self.backward_search(pattern) {
+            Some((begin, end)) => (begin..end).map(|row| self.text_position(row)).collect(),
+            None => Vec::new()
+        };
+
+        result.sort_unstable();
+        result
+    }
+
+    // Range of rows prefixed with the pattern, or `None` if it does not occur.
+    fn backward_search(&self, pattern: &[u8]) -> Option<(usize, usize)> {
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let mut begin = 0;
+        let mut end = self.last_column.len();
+
+        for &byte in pattern.iter().rev() {
+            self.alphabet[byte as usize]?;
+            begin = self.counts[byte as usize] + self.rank(byte, begin);
+            end = self.counts[byte as usize] + self.rank(byte, end);
+
+            if begin >= end {
+                return None;
+            }
+        }
+
+        Some((begin, end))
+    }
+
+    // Number of occurrences of the byte in the first `row` entries of the last column.
+    fn rank(&self, byte: u8, row: usize) -> usize {
+        let symbol = self.alphabet[byte as usize].unwrap();
+        let checkpoint = row / self.occ_sample_rate;
+        let start = checkpoint * self.occ_sample_rate;
+
+        let scanned = self.last_column[start..row].iter().enumerate()
+            .filter(|&(offset, &value)| value == byte && start + offset != self.sentinel)
+            .count();
+
+        self.occurrences[checkpoint * self.alphabet_size + symbol] + scanned
+    }
+
+    // Walks the last-to-first mapping until reaching a row with a sampled position.
+    fn text_position(&self, mut row: usize) -> usize {
+        let mut steps = 0;
+
+        while !self.sampled_rows.get(row) {
+            let byte = self.last_column[row];
+            row = self.counts[byte as usize] + self.rank(byte, row);
+            steps += 1;
+        }
+
+        self.sa_samples[self.sampled_rows.rank(row)] + steps
+    }
+}
+
+fn bwt_from_suffixes(text: &[u8], suffixes: &[usize]) -> Bwt {
+    let mut last_column = Vec::with_capacity(text.len() + 1);
+    let mut sentinel = 0;
+
+    // The first row is the suffix consisting of the sentinel only.
+    last_column.push(text.last().copied().unwrap_or(0));
+    if text.is_empty() {
+        return Bwt { last_column, sentinel };
+    }
+
+    for (row, &position) in suffixes.iter().enumerate() {
+        if position == 0 {
+            sentinel = row + 1;
+            last_column.push(0);
+        }
+        else {
+            last_column.push(text[position - 1]);
+        }
+    }
+
+    Bwt { last_column, sentinel }
+}
+
+fn count_smaller(last_column: &[u8], sentinel: usize) -> [usize; 256] {
+    let mut frequencies = [0; 256];
+    for (row, &byte) in last_column.iter().enumerate() {
+        if row != sentinel {
+            frequencies[byte as usize] += 1;
+        }
+    }
+
+    let mut counts = [0; 256];
+    let mut total = 1;
+    for byte in 0..256 {
+        counts[byte] = total;
+        total += frequencies[byte];
+    }
+
+    counts
+}
+
+// Bit vector with constant-time rank queries over 64-bit words.
+struct RankBitVector {
+    words: Vec<u64>,
+    ranks: Vec<usize>
+}
+
+impl RankBitVector {
+    fn new(length: usize) -> Self {
+        RankBitVector { words: vec![0; length.div_ceil(64)], ranks: Vec::new() }
+    }
+
+    fn set(&mut self, index: usize) {
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    fn get(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    fn build_ranks(&mut self) {
+        let mut total = 0;
+        self.ranks = self.words.iter().map(|word| {
+            let rank = total;
+            total += word.count_ones() as usize;
+            rank
+        }).collect();
+    }
+
+    // Number of set bits before the index.
+    fn rank(&self, index: usize) -> usize {
+        let mask = (1u64 << (index % 64)) - 1;
+        self.ranks[index / 64] + (self.words[index / 64] & mask).count_ones() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::StreamSearcher;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn bwt_banana_test() {
+        let transform = bwt(b"banana");
+
+        assert_eq!(Bwt { last_column: b"annb\0aa".to_vec(), sentinel: 4 }, transform);
+        assert_eq!(b"banana".to_vec(), inverse_bwt(&transform));
+    }
+
+    #[test]
+    fn bwt_empty_test() {
+        assert_eq!(Vec::<u8>::new(), inverse_bwt(&bwt(b"")));
+        assert_eq!(Vec::<u8>::new(), inverse_bwt(&Bwt { last_column: Vec::new(), sentinel: 0 }));
+
+        let index = FmIndex::new(b"");
+        assert_eq!(0, index.count(b"a"));
+        assert!(index.locate(b"a").is_empty());
+
+        // The empty pattern has no occurrences, like in the other text indexes.
+        let index = FmIndex::new(b"ab");
+        assert_eq!(0, index.count(b""));
+        assert!(index.locate(b"").is_empty());
+    }
+
+    #[test]
+    fn fm_index_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(31);
+
+        for (occ_sample_rate, sa_sample_rate) in [(1, 1), (3, 5), (64, 32)] {
+            for _ in 0..50 {
+                let length = rng.gen_range(1..300);
+                let text: Vec<u8> = (0..length).map(|_| b"ACGT"[rng.gen_range(0..4)]).collect();
+                assert_eq!(text, inverse_bwt(&bwt(&text)));
+
+                let index = FmIndex::with_sampling(&text, occ_sample_rate, sa_sample_rate);
+                for _ in 0..10 {
+                    let pattern: Vec<u8> = (0..rng.gen_range(1..5)).map(|_| b"ACGTN"[rng.gen_range(0..5)]).collect();
+                    let expected = StreamSearcher::new(&pattern).feed(&text);
+
+                    assert_eq!(expected.len(), index.count(&pattern));
+                    assert_eq!(expected, index.locate(&pattern));
+                }
+            }
+        }
+    }
+}
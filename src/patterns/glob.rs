@@ -0,0 +1,513 @@
+use std::fmt;
+use std::ops::Range;
+use super::{KmpMatcher, Matcher};
+
+/// Glob pattern for paths: `?` matches a single character, `*` any (possibly empty) run of
+/// characters, `[a-z]` a character from a class (`[!a-z]` or `[^a-z]` negates it) and `\` escapes
+/// the next character. None of these match `/`, so `src/*.rs` only matches files directly in
+/// `src`; `**` matches any run including slashes, and in `/**/` or a leading `**/` it may also
+/// match no directories at all, so `src/**/*.rs` matches every Rust file under `src`.
+///
+/// The pattern is split on `**` into groups, and on `/` and `*` into segments. A group is placed
+/// at the leftmost slash of the text where it fits, and within a path component the segments are
+/// placed greedily from the left, so matching takes O(nm) in the worst case and never
+/// backtracks. Literal segments are searched with Knuth-Morris-Pratt algorithm.
+pub struct Glob {
+    groups: Vec<Group>
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GlobError {
+    /// A class opened at the given position is not closed.
+    UnclosedClass(usize),
+    /// A class range at the given position has its bounds reversed.
+    InvalidRange(usize),
+    /// The pattern ends with an escape character.
+    TrailingEscape
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GlobError::UnclosedClass(position) => write!(f, "unclosed class at {}", position),
+            GlobError::InvalidRange(position) => write!(f, "invalid class range at {}", position),
+            GlobError::TrailingEscape => write!(f, "pattern ends with an escape")
+        }
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+enum Token {
+    Char(char),
+    AnyChar,
+    Class { ranges: Vec<(char, char)>, negated: bool }
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::AnyChar => c != '/',
+            Token::Class { ranges, negated } => c != '/'
+                && ranges.iter().any(|&(low, high)| low <= c && c <= high) != *negated
+        }
+    }
+}
+
+struct Segment {
+    tokens: Vec<Token>,
+    // Present when the segment has no wildcards.
+    literal: Option<KmpMatcher>
+}
+
+impl Segment {
+    fn new(tokens: Vec<Token>) -> Self {
+        let literal: Option<String> = tokens.iter()
+            .map(|token| match token { Token::Char(c) => Some(*c), _ => None })
+            .collect();
+
+        Segment { literal: literal.map(|literal| KmpMatcher::compile(&literal)), tokens }
+    }
+
+    fn matches_at(&self, text: &[char], position: usize) -> bool {
+        position + self.tokens.len() <= text.len()
+            && self.tokens.iter().zip(&text[position..]).all(|(token, &c)| token.matches(c))
+    }
+
+    // Leftmost position in `text[from..to]` where the whole segment fits.
+    fn find(&self, text: &Text, from: usize, to: usize) -> Option<usize> {
+        if self.tokens.is_empty() {
+            return Some(from);
+        }
+        if from + self.tokens.len() > to {
+            return None;
+        }
+
+        match &self.literal {
+            Some(matcher) => matcher.find_first(text.slice(from, to)).map(|span| from + span.start),
+            None => (from..=to - self.tokens.len()).find(|&position| self.matches_at(&text.chars, position))
+        }
+    }
+}
+
+// Places the segments, separated by stars, in `text[from..to]`. The first one starts at `from`
+// unless `free_start` and the last one ends at `to` unless `free_end`; the stars take the
+// shortest runs. The returned span starts at the first nonempty segment.
+fn place_segments(segments: &[Segment], text: &Text, from: usize, to: usize, free_start: bool, free_end: bool)
+    -> Option<Range<usize>> {
+    let mut middle = segments;
+    let mut position = from;
+    let mut end = to;
+    let mut start = None;
+    let mut last_start = None;
+
+    if !free_start {
+        let (first, rest) = middle.split_first().unwrap();
+        if from + first.tokens.len() > to || !first.matches_at(&text.chars, from) {
+            return None;
+        }
+        position += first.tokens.len();
+        start = Some(from);
+        middle = rest;
+    }
+
+    if !free_end {
+        match middle.split_last() {
+            Some((last, rest)) => {
+                end = to.checked_sub(last.tokens.len()).filter(|&end| end >= position)?;
+                if !last.matches_at(&text.chars, end) {
+                    return None;
+                }
+                if !last.tokens.is_empty() {
+                    last_start = Some(end);
+                }
+                middle = rest;
+            },
+            None if position != to => return None,
+            None => {}
+        }
+    }
+
+    for segment in middle {
+        let found = segment.find(text, position, end)?;
+        if start.is_none() && !segment.tokens.is_empty() {
+            start = Some(found);
+        }
+        position = found + segment.tokens.len();
+    }
+
+    let stop = if free_end { position } else { to };
+    Some(start.or(last_start).unwrap_or(stop)..stop)
+}
+
+// Part of the pattern between double stars.
+struct Group {
+    // Segments of every path component, split on slashes.
+    components: Vec<Vec<Segment>>,
+    // Set after `/**/` or a leading `**/`, where the group has to start a path component.
+    at_boundary: bool
+}
+
+impl Group {
+    fn is_empty(&self) -> bool {
+        self.components.len() == 1 && self.components[0].iter().all(|segment| segment.tokens.is_empty())
+    }
+
+    // Places the group at `from` if `anchored_start`, or else at the leftmost position after it,
+    // which also gives the earliest end. With `anchored_end` the group has to end the text.
+    fn place(&self, text: &Text, from: usize, anchored_start: bool, anchored_end: bool) -> Option<Range<usize>> {
+        // The first component of the group goes to some text component k, which holds `from`
+        // or follows it, and the rest go to the text components right after it.
+        let first = text.slashes.partition_point(|&slash| slash < from);
+        let last = text.slashes.len().checked_sub(self.components.len() - 1)?;
+        let low = if anchored_end { last.max(first) } else { first };
+        let high = if anchored_start { first.min(last) } else { last };
+
+        (low..=high).find_map(|k| self.place_at(text, from, k, anchored_start, anchored_end))
+    }
+
+    fn place_at(&self, text: &Text, from: usize, k: usize, anchored_start: bool, anchored_end: bool)
+        -> Option<Range<usize>> {
+        let boundary = text.component(k).start;
+        if self.at_boundary && !anchored_start && boundary < from {
+            return None;
+        }
+
+        let count = self.components.len();
+        let mut span = 0..0;
+        for (i, segments) in self.components.iter().enumerate() {
+            let component = text.component(k + i);
+            let free_start = i == 0 && !anchored_start && !self.at_boundary;
+            let free_end = i == count - 1 && !anchored_end;
+            let placed = place_segments(segments, text, component.start.max(from), component.end, free_start, free_end)?;
+
+            if i == 0 {
+                span.start = placed.start;
+            }
+            span.end = placed.end;
+        }
+
+        Some(span)
+    }
+}
+
+// Text indexed both by characters and, for the KMP matchers, by bytes.
+struct Text<'a> {
+    text: &'a str,
+    chars: Vec<char>,
+    byte_offsets: Vec<usize>,
+    slashes: Vec<usize>
+}
+
+impl<'a> Text<'a> {
+    fn new(text: &'a str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let mut byte_offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        byte_offsets.push(text.len());
+        let slashes = (0..chars.len()).filter(|&i| chars[i] == '/').collect();
+
+        Text { text, chars, byte_offsets, slashes }
+    }
+
+    fn slice(&self, from: usize, to: usize) -> &'a str {
+        &self.text[self.byte_offsets[from]..self.byte_offsets[to]]
+    }
+
+    // Range of the k-th path component, between slashes.
+    fn component(&self, k: usize) -> Range<usize> {
+        let start = if k == 0 { 0 } else { self.slashes[k - 1] + 1 };
+        start..self.slashes.get(k).copied().unwrap_or(self.chars.len())
+    }
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, GlobError> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut groups = Vec::new();
+        let mut components = Vec::new();
+        let mut segments = Vec::new();
+        let mut tokens = Vec::new();
+        let mut at_boundary = false;
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '*' => {
+                    segments.push(Segment::new(std::mem::take(&mut tokens)));
+                    let run = chars[i..].iter().take_while(|&&c| c == '*').count();
+                    if run > 1 {
+                        components.push(std::mem::take(&mut segments));
+                        groups.push(Group { components: std::mem::take(&mut components), at_boundary });
+
+                        // A slash after a double star which starts a component is absorbed
+                        // into it, so that it can match no directories.
+                        at_boundary = (i == 0 || chars[i - 1] == '/') && chars.get(i + run) == Some(&'/');
+                        i += run - 1 + usize::from(at_boundary);
+                    }
+                },
+                '?' => tokens.push(Token::AnyChar),
+                '\\' if chars.get(i + 1) != Some(&'/') => {
+                    i += 1;
+                    tokens.push(Token::Char(*chars.get(i).ok_or(GlobError::TrailingEscape)?));
+                },
+                '\\' | '/' => {
+                    i += usize::from(chars[i] == '\\');
+                    segments.push(Segment::new(std::mem::take(&mut tokens)));
+                    components.push(std::mem::take(&mut segments));
+                },
+                '[' => {
+                    let (token, end) = parse_class(&chars, i)?;
+                    tokens.push(token);
+                    i = end;
+                },
+                c => tokens.push(Token::Char(c))
+            }
+
+            i += 1;
+        }
+
+        segments.push(Segment::new(tokens));
+        components.push(segments);
+        groups.push(Group { components, at_boundary });
+        Ok(Glob { groups })
+    }
+
+    /// Checks if the whole text matches the pattern.
+    pub fn matches(&self, text: &str) -> bool {
+        let text = Text::new(text);
+        let last = self.groups.len() - 1;
+        let mut position = 0;
+
+        for (i, group) in self.groups.iter().enumerate() {
+            match group.place(&text, position, i == 0, i == last) {
+                Some(span) => position = span.end,
+                None => return false
+            }
+        }
+
+        true
+    }
+
+    /// Finds the leftmost substring of the text matching the pattern, taking the shortest
+    /// runs for the stars. Leading and trailing stars do not extend the match.
+    pub fn find(&self, text: &str) -> Option<Range<usize>> {
+        let text = Text::new(text);
+        let mut groups = self.groups.iter().skip_while(|group| group.is_empty());
+        let first = match groups.next() {
+            Some(first) => first,
+            None => return Some(0..0)
+        };
+
+        let span = first.place(&text, 0, false, false)?;
+        let mut end = span.end;
+        for group in groups {
+            end = group.place(&text, end, false, false)?.end;
+        }
+
+        Some(span.start..end)
+    }
+}
+
+/// Finds the leftmost occurrence of a pattern in which the `gap` character matches any
+/// (possibly empty) run of characters (CLRS exercise 32.1-4).
+pub fn find_with_gaps(pattern: &str, gap: char, text: &str) -> Option<Range<usize>> {
+    let segments: Vec<Segment> = pattern.split(gap)
+        .map(|segment| Segment::new(segment.chars().map(Token::Char).collect()))
+        .collect();
+
+    let text = Text::new(text);
+    // The first segment is found at its leftmost position; if the rest fits after it,
+    // no match can start earlier.
+    place_segments(&segments, &text, 0, text.chars.len(), true, true)
+}
+
+// Parses a class starting at `start` (the opening bracket) and returns it with the position
+// of the closing bracket.
+fn parse_class(chars: &[char], start: usize) -> Result<(Token, usize), GlobError> {
+    let mut ranges = Vec::new();
+    let mut i = start + 1;
+    let negated = matches!(chars.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    // A leading ']' is a literal.
+    let first = i;
+    loop {
+        let mut low = *chars.get(i).ok_or(GlobError::UnclosedClass(start))?;
+        if low == ']' && i > first {
+            return Ok((Token::Class { ranges, negated }, i));
+        }
+        if low == '\\' {
+            i += 1;
+            low = *chars.get(i).ok_or(GlobError::UnclosedClass(start))?;
+        }
+
+        let mut high = low;
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&c| c != ']') {
+            let range = i;
+            i += 2;
+            high = chars[i];
+            if high == '\\' {
+                i += 1;
+                high = *chars.get(i).ok_or(GlobError::UnclosedClass(start))?;
+            }
+            if high < low {
+                return Err(GlobError::InvalidRange(range));
+            }
+        }
+
+        ranges.push((low, high));
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    // Backtracking matcher with the same semantics, for small inputs. Classes are always `[ab]`.
+    fn reference_matches(pattern: &[char], i: usize, text: &[char], j: usize) -> bool {
+        if i == pattern.len() {
+            return j == text.len();
+        }
+
+        match pattern[i] {
+            '*' => {
+                let run = pattern[i..].iter().take_while(|&&c| c == '*').count();
+                if run == 1 {
+                    (j..=text.len())
+                        .take_while(|&k| k == j || text[k - 1] != '/')
+                        .any(|k| reference_matches(pattern, i + 1, text, k))
+                }
+                else {
+                    let after = i + run;
+                    (i == 0 || pattern[i - 1] == '/') && pattern.get(after) == Some(&'/')
+                        && reference_matches(pattern, after + 1, text, j)
+                        || (j..=text.len()).any(|k| reference_matches(pattern, after, text, k))
+                }
+            },
+            '?' => j < text.len() && text[j] != '/' && reference_matches(pattern, i + 1, text, j + 1),
+            '[' => j < text.len() && "ab".contains(text[j]) && reference_matches(pattern, i + 4, text, j + 1),
+            c => j < text.len() && text[j] == c && reference_matches(pattern, i + 1, text, j + 1)
+        }
+    }
+
+    #[test]
+    fn matches_test() {
+        let glob = Glob::new("src/*.rs").unwrap();
+        assert!(glob.matches("src/main.rs"));
+        assert!(!glob.matches("src/patterns/glob.rs"));
+        assert!(glob.matches("src/.rs"));
+        assert!(!glob.matches("src/main.rsx"));
+        assert!(!glob.matches("lib/main.rs"));
+
+        let glob = Glob::new("file?.[ch]").unwrap();
+        assert!(glob.matches("file1.c"));
+        assert!(glob.matches("fileż.h"));
+        assert!(!glob.matches("file.c"));
+        assert!(!glob.matches("file1.o"));
+
+        let glob = Glob::new("*[!0-9]*ab*").unwrap();
+        assert!(glob.matches("12x3ab"));
+        assert!(!glob.matches("1234ab"));
+
+        let glob = Glob::new("a*a").unwrap();
+        assert!(glob.matches("aa"));
+        assert!(!glob.matches("a"));
+
+        assert!(Glob::new("").unwrap().matches(""));
+        assert!(Glob::new("*").unwrap().matches(""));
+        assert!(Glob::new("\\*").unwrap().matches("*"));
+        assert!(!Glob::new("\\*").unwrap().matches("x"));
+    }
+
+    #[test]
+    fn paths_test() {
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        assert!(glob.matches("src/main.rs"));
+        assert!(glob.matches("src/patterns/glob.rs"));
+        assert!(glob.matches("src/a/b/c.rs"));
+        assert!(!glob.matches("lib/main.rs"));
+        assert!(!glob.matches("src/main.rs/x"));
+        assert!(!glob.matches("srcmain.rs"));
+
+        let glob = Glob::new("**/test_*").unwrap();
+        assert!(glob.matches("test_glob"));
+        assert!(glob.matches("a/b/test_glob"));
+        assert!(!glob.matches("a/my_test_glob"));
+        assert!(!glob.matches("test_a/b"));
+
+        let glob = Glob::new("target/**").unwrap();
+        assert!(glob.matches("target/debug/cormen"));
+        assert!(glob.matches("target/"));
+        assert!(!glob.matches("target"));
+
+        assert!(Glob::new("a**b").unwrap().matches("a/x/b"));
+        assert!(Glob::new("a\\/b").unwrap().matches("a/b"));
+        assert!(!Glob::new("a?b").unwrap().matches("a/b"));
+        assert!(!Glob::new("a[!x]b").unwrap().matches("a/b"));
+        assert!(!Glob::new("a[--0]b").unwrap().matches("a/b"));
+
+        assert_eq!(Some(4..13), Glob::new("b/*.rs").unwrap().find("lib/b/main.rs"));
+        assert_eq!(Some(6..10), Glob::new("**/main*").unwrap().find("a/b/c/main.rs"));
+        assert_eq!(None, Glob::new("a*b").unwrap().find("xa/bx"));
+    }
+
+    #[test]
+    fn matches_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(34);
+        let pieces = ["a", "b", "/", "?", "*", "**", "[ab]"];
+
+        for _ in 0..3000 {
+            let pattern: String = (0..rng.gen_range(0..8)).map(|_| pieces[rng.gen_range(0..pieces.len())]).collect();
+            let text: String = (0..rng.gen_range(0..10)).map(|_| ['a', 'b', '/'][rng.gen_range(0..3)]).collect();
+
+            let expected = reference_matches(&pattern.chars().collect::<Vec<char>>(), 0,
+                                             &text.chars().collect::<Vec<char>>(), 0);
+            assert_eq!(expected, Glob::new(&pattern).unwrap().matches(&text), "{} on {}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn no_blowup_test() {
+        // A backtracking matcher needs exponential time on this one.
+        let glob = Glob::new(&format!("{}b", "a*".repeat(40))).unwrap();
+        assert!(!glob.matches(&"a".repeat(5000)));
+        assert!(glob.matches(&format!("{}b", "a".repeat(5000))));
+    }
+
+    #[test]
+    fn find_test() {
+        let glob = Glob::new("b?d*f").unwrap();
+        assert_eq!(Some(1..6), glob.find("abcdefbcdf"));
+        assert_eq!(None, glob.find("abcde"));
+        assert_eq!(Some(0..0), Glob::new("**").unwrap().find("abc"));
+    }
+
+    #[test]
+    fn find_with_gaps_test() {
+        // CLRS example: ab◊ba◊c occurs in cabccbacbacab.
+        assert_eq!(Some(1..8), find_with_gaps("ab◊ba◊c", '◊', "cabccbacbacab"));
+        assert_eq!(None, find_with_gaps("ab◊ba◊c", '◊', "cabccbbab"));
+        assert_eq!(Some(2..3), find_with_gaps("◊b◊", '◊', "aaba"));
+    }
+
+    #[test]
+    fn errors_test() {
+        assert_eq!(Some(GlobError::UnclosedClass(2)), Glob::new("ab[cd").err());
+        assert_eq!(Some(GlobError::InvalidRange(1)), Glob::new("[z-a]").err());
+        assert_eq!(Some(GlobError::TrailingEscape), Glob::new("ab\\").err());
+        assert!(Glob::new("[]]").unwrap().matches("]"));
+        assert_eq!(Some(GlobError::InvalidRange(1)), Glob::new("[z-\\a]").err());
+        assert_eq!(Some(GlobError::UnclosedClass(0)), Glob::new("[a-\\").err());
+
+        let glob = Glob::new("[+-\\]]").unwrap();
+        assert!(glob.matches("]"));
+        assert!(glob.matches("5"));
+        assert!(!glob.matches("a"));
+    }
+}
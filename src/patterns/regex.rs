@@ -0,0 +1,866 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use super::Matcher;
+
+/// Regular expression compiled to a Thompson NFA and simulated without backtracking, so
+/// every search takes O(nm) time for a text of length n and a pattern of size m.
+/// Supports concatenation, alternation `|`, grouping `()`, repetitions `*`, `+`, `?`,
+/// the wildcard `.`, classes like `[a-z_]` or `[^0-9]`, escapes `\d`, `\w`, `\s` and anchors
+/// `^`, `$`. Matches are leftmost-longest and offsets are character positions.
+pub struct Regex {
+    program: Vec<Instruction>,
+    // Program of the reversed expression, for scanning from right to left.
+    reverse: Vec<Instruction>
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RegexError {
+    /// The pattern ended in the middle of an escape or a class.
+    UnexpectedEnd,
+    /// A parenthesis at the given position has no counterpart.
+    UnmatchedParenthesis(usize),
+    /// A repetition operator at the given position does not follow an expression.
+    NothingToRepeat(usize),
+    /// A class range at the given position has its bounds reversed.
+    InvalidRange(usize),
+    /// The group opened at the given position is nested deeper than `Regex::MAX_NESTING`.
+    NestingTooDeep(usize)
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegexError::UnexpectedEnd => write!(f, "unexpected end of pattern"),
+            RegexError::UnmatchedParenthesis(position) => write!(f, "unmatched parenthesis at {}", position),
+            RegexError::NothingToRepeat(position) => write!(f, "nothing to repeat at {}", position),
+            RegexError::InvalidRange(position) => write!(f, "invalid class range at {}", position),
+            RegexError::NestingTooDeep(position) => write!(f, "groups nested too deeply at {}", position)
+        }
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+#[derive(Debug, Clone)]
+struct Class {
+    ranges: Vec<(char, char)>,
+    negated: bool
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(low, high)| low <= c && c <= high) != self.negated
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Literal(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Star(Box<Node>),
+    Plus(Box<Node>),
+    Question(Box<Node>)
+}
+
+#[derive(Debug)]
+enum Instruction {
+    Char(char),
+    Any,
+    Class(Class),
+    AssertStart,
+    AssertEnd,
+    Split(usize, usize),
+    Jump(usize),
+    Match
+}
+
+impl Instruction {
+    fn consumes(&self, c: char) -> bool {
+        match self {
+            Instruction::Char(expected) => *expected == c,
+            Instruction::Any => true,
+            Instruction::Class(class) => class.matches(c),
+            _ => false
+        }
+    }
+}
+
+// Parsing, reversing and compiling recurse over the nesting of groups, which is limited,
+// so a pattern cannot exhaust the stack. Chains of repetitions are collapsed into one.
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    depth: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Result<char, RegexError> {
+        let c = self.peek().ok_or(RegexError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(c)
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, RegexError> {
+        let mut branches = vec![self.parse_concatenation()?];
+        while self.peek() == Some('|') {
+            self.position += 1;
+            branches.push(self.parse_concatenation()?);
+        }
+
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Node::Alternate(branches) })
+    }
+
+    fn parse_concatenation(&mut self) -> Result<Node, RegexError> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+
+            items.push(self.parse_repetition()?);
+        }
+
+        Ok(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap(),
+            _ => Node::Concat(items)
+        })
+    }
+
+    fn parse_repetition(&mut self) -> Result<Node, RegexError> {
+        let node = self.parse_atom()?;
+
+        // Any two repetitions make a star, unless both are the same one.
+        let mut repetition = None;
+        while let Some(c) = self.peek() {
+            if !matches!(c, '*' | '+' | '?') {
+                break;
+            }
+            repetition = match repetition {
+                None => Some(c),
+                Some(previous) if previous == c => Some(c),
+                Some(_) => Some('*')
+            };
+            self.position += 1;
+        }
+
+        Ok(match repetition {
+            Some('*') => Node::Star(Box::new(node)),
+            Some('+') => Node::Plus(Box::new(node)),
+            Some(_) => Node::Question(Box::new(node)),
+            None => node
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        let position = self.position;
+        match self.next()? {
+            '(' => {
+                if self.depth == Regex::MAX_NESTING {
+                    return Err(RegexError::NestingTooDeep(position));
+                }
+
+                self.depth += 1;
+                let node = self.parse_alternation()?;
+                self.depth -= 1;
+                if self.peek() != Some(')') {
+                    return Err(RegexError::UnmatchedParenthesis(position));
+                }
+
+                self.position += 1;
+                Ok(node)
+            },
+            ')' => Err(RegexError::UnmatchedParenthesis(position)),
+            '*' | '+' | '?' => Err(RegexError::NothingToRepeat(position)),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '[' => self.parse_class().map(Node::Class),
+            '\\' => {
+                let c = self.next()?;
+                Ok(match shorthand_class(c) {
+                    Some(class) => Node::Class(class),
+                    None => Node::Literal(c)
+                })
+            },
+            c => Ok(Node::Literal(c))
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Class, RegexError> {
+        let mut class = Class { ranges: Vec::new(), negated: false };
+        if self.peek() == Some('^') {
+            class.negated = true;
+            self.position += 1;
+        }
+
+        // A leading ']' is a literal, as in POSIX.
+        let mut first = true;
+        loop {
+            let position = self.position;
+            let mut low = self.next()?;
+            if low == ']' && !first {
+                return Ok(class);
+            }
+            first = false;
+
+            if low == '\\' {
+                low = self.next()?;
+                if let Some(shorthand) = shorthand_class(low) {
+                    class.ranges.extend(shorthand.ranges);
+                    continue;
+                }
+            }
+
+            let mut high = low;
+            if self.peek() == Some('-') && self.chars.get(self.position + 1).is_some_and(|&c| c != ']') {
+                self.position += 1;
+                high = self.next()?;
+                if high == '\\' {
+                    high = self.next()?;
+                }
+
+                if high < low {
+                    return Err(RegexError::InvalidRange(position));
+                }
+            }
+
+            class.ranges.push((low, high));
+        }
+    }
+}
+
+fn shorthand_class(c: char) -> Option<Class> {
+    let ranges = match c {
+        'd' => vec![('0', '9')],
+        'w' => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+        's' => vec![(' ', ' '), ('\t', '\r')],
+        _ => return None
+    };
+
+    Some(Class { ranges, negated: false })
+}
+
+// The expression matching the reversed strings.
+fn reversed(node: &Node) -> Node {
+    match node {
+        Node::Empty => Node::Empty,
+        Node::Literal(c) => Node::Literal(*c),
+        Node::Any => Node::Any,
+        Node::Class(class) => Node::Class(class.clone()),
+        Node::Start => Node::Start,
+        Node::End => Node::End,
+        Node::Concat(items) => Node::Concat(items.iter().rev().map(reversed).collect()),
+        Node::Alternate(branches) => Node::Alternate(branches.iter().map(reversed).collect()),
+        Node::Star(inner) => Node::Star(Box::new(reversed(inner))),
+        Node::Plus(inner) => Node::Plus(Box::new(reversed(inner))),
+        Node::Question(inner) => Node::Question(Box::new(reversed(inner)))
+    }
+}
+
+// Thompson's construction: every node becomes a fragment with a single entry and exit.
+fn compile(node: &Node, program: &mut Vec<Instruction>) {
+    match node {
+        Node::Empty => {},
+        Node::Literal(c) => program.push(Instruction::Char(*c)),
+        Node::Any => program.push(Instruction::Any),
+        Node::Class(class) => program.push(Instruction::Class(class.clone())),
+        Node::Start => program.push(Instruction::AssertStart),
+        Node::End => program.push(Instruction::AssertEnd),
+        Node::Concat(items) => {
+            for item in items {
+                compile(item, program);
+            }
+        },
+        Node::Alternate(branches) => {
+            let mut jumps = Vec::new();
+            for (i, branch) in branches.iter().enumerate() {
+                if i + 1 < branches.len() {
+                    let split = program.len();
+                    program.push(Instruction::Split(split + 1, 0));
+                    compile(branch, program);
+                    jumps.push(program.len());
+                    program.push(Instruction::Jump(0));
+                    program[split] = Instruction::Split(split + 1, program.len());
+                }
+                else {
+                    compile(branch, program);
+                }
+            }
+
+            for jump in jumps {
+                program[jump] = Instruction::Jump(program.len());
+            }
+        },
+        Node::Star(inner) => {
+            let split = program.len();
+            program.push(Instruction::Split(split + 1, 0));
+            compile(inner, program);
+            program.push(Instruction::Jump(split));
+            program[split] = Instruction::Split(split + 1, program.len());
+        },
+        Node::Plus(inner) => {
+            let start = program.len();
+            compile(inner, program);
+            program.push(Instruction::Split(start, program.len() + 1));
+        },
+        Node::Question(inner) => {
+            let split = program.len();
+            program.push(Instruction::Split(split + 1, 0));
+            compile(inner, program);
+            program[split] = Instruction::Split(split + 1, program.len());
+        }
+    }
+}
+
+// Ordered set of NFA threads, at most one per instruction.
+struct Threads {
+    list: Vec<(usize, usize)>,
+    seen: Vec<bool>
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Threads { list: Vec::with_capacity(size), seen: vec![false; size] }
+    }
+
+    fn clear(&mut self) {
+        self.seen.fill(false);
+        self.list.clear();
+    }
+}
+
+// Follows empty transitions from `pc` and appends the reached threads.
+fn add_thread(program: &[Instruction], threads: &mut Threads, pc: usize, start: usize, position: usize, length: usize) {
+    let mut stack = vec![pc];
+
+    while let Some(pc) = stack.pop() {
+        if threads.seen[pc] {
+            continue;
+        }
+        threads.seen[pc] = true;
+
+        match program[pc] {
+            Instruction::Jump(target) => stack.push(target),
+            Instruction::Split(first, second) => {
+                stack.push(second);
+                stack.push(first);
+            },
+            Instruction::AssertStart => {
+                if position == 0 {
+                    stack.push(pc + 1);
+                }
+            },
+            Instruction::AssertEnd => {
+                if position == length {
+                    stack.push(pc + 1);
+                }
+            },
+            _ => threads.list.push((pc, start))
+        }
+    }
+}
+
+impl Regex {
+    /// The deepest allowed nesting of groups.
+    pub const MAX_NESTING: usize = 256;
+
+    pub fn new(pattern: &str) -> Result<Regex, RegexError> {
+        let mut parser = Parser { chars: pattern.chars().collect(), position: 0, depth: 0 };
+        let node = parser.parse_alternation()?;
+        if parser.position < parser.chars.len() {
+            return Err(RegexError::UnmatchedParenthesis(parser.position));
+        }
+
+        let mut program = Vec::new();
+        compile(&node, &mut program);
+        program.push(Instruction::Match);
+
+        let mut reverse = Vec::new();
+        compile(&reversed(&node), &mut reverse);
+        reverse.push(Instruction::Match);
+
+        Ok(Regex { program, reverse })
+    }
+
+    /// Checks if any substring of the text matches, reading the text only up to the first
+    /// match.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find_at(text.chars(), 0, true).is_some()
+    }
+
+    /// Finds the leftmost-longest match, reading the text only as far as it may extend.
+    pub fn find(&self, text: &str) -> Option<Range<usize>> {
+        self.find_at(text.chars(), 0, false)
+    }
+
+    /// Finds all non-overlapping leftmost-longest matches. An empty match directly after
+    /// the previous match is skipped. Takes O(nm) time in total.
+    pub fn find_spans(&self, text: &str) -> Vec<Range<usize>> {
+        let chars: Vec<char> = text.chars().collect();
+        let longest = self.longest_matches(&chars);
+        let mut result: Vec<Range<usize>> = Vec::new();
+        let mut from = 0;
+
+        while from <= chars.len() {
+            match longest[from] {
+                Some(end) => {
+                    let span = from..end;
+                    from = if span.is_empty() { span.end + 1 } else { span.end };
+                    if !(span.is_empty() && result.last().is_some_and(|last| last.end == span.start)) {
+                        result.push(span);
+                    }
+                },
+                None => from += 1
+            }
+        }
+
+        result
+    }
+
+    /// Start offsets of all matches, overlapping ones included, like the other finders in
+    /// `patterns`.
+    pub fn find_all(&self, text: &str) -> Vec<usize> {
+        self.find_iter(text).map(|span| span.start).collect()
+    }
+
+    /// Creates a lazily built DFA for this expression. At most `cache_limit` states are
+    /// kept; when the limit is reached the cache is flushed and built again.
+    pub fn lazy_dfa(&self, cache_limit: usize) -> LazyDfa<'_> {
+        LazyDfa {
+            regex: self,
+            cache_limit: cache_limit.max(2),
+            states: Vec::new(),
+            ids: HashMap::new(),
+            cache_flushes: 0
+        }
+    }
+
+    // The end of the longest match starting at every position, if any. The reversed program
+    // runs from right to left with a thread seeded at every position. Threads meeting at an
+    // instruction have the same future, so keeping the one with the farthest end suffices.
+    fn longest_matches(&self, text: &[char]) -> Vec<Option<usize>> {
+        let mut current = Threads::new(self.reverse.len());
+        let mut next = Threads::new(self.reverse.len());
+        let mut result = vec![None; text.len() + 1];
+
+        for position in (0..=text.len()).rev() {
+            // Threads are ordered by decreasing end, the new one has the nearest.
+            add_thread(&self.reverse, &mut current, 0, position, position, text.len());
+            result[position] = current.list.iter()
+                .find(|&&(pc, _)| matches!(self.reverse[pc], Instruction::Match))
+                .map(|&(_, end)| end);
+
+            if position == 0 {
+                break;
+            }
+
+            for &(pc, end) in &current.list {
+                if self.reverse[pc].consumes(text[position - 1]) {
+                    add_thread(&self.reverse, &mut next, pc + 1, end, position - 1, text.len());
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        result
+    }
+
+    // Pike VM over the characters from position `from` on, which reads only as far as needed.
+    // Threads are ordered by their start, so keeping the first thread for every instruction
+    // keeps the leftmost one. With `any`, returns the first match found, which may be shorter.
+    fn find_at<I: Iterator<Item = char>>(&self, text: I, from: usize, any: bool) -> Option<Range<usize>> {
+        let mut text = text.peekable();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut best: Option<Range<usize>> = None;
+        // The length of the text is known only at its end; until then no position is the end.
+        let mut length = if text.peek().is_none() { from } else { usize::MAX };
+        let mut position = from;
+
+        loop {
+            if best.is_none() {
+                add_thread(&self.program, &mut current, 0, position, position, length);
+            }
+
+            for &(pc, start) in &current.list {
+                if matches!(self.program[pc], Instruction::Match) {
+                    let better = match &best {
+                        None => true,
+                        Some(span) => start < span.start || (start == span.start && position > span.end)
+                    };
+
+                    if better {
+                        best = Some(start..position);
+                    }
+                }
+            }
+
+            if any && best.is_some() {
+                break;
+            }
+            let Some(c) = text.next() else {
+                break;
+            };
+            if text.peek().is_none() {
+                length = position + 1;
+            }
+
+            for i in 0..current.list.len() {
+                let (pc, start) = current.list[i];
+                if best.as_ref().is_some_and(|span| start > span.start) {
+                    continue;
+                }
+
+                if self.program[pc].consumes(c) {
+                    add_thread(&self.program, &mut next, pc + 1, start, position + 1, length);
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+            position += 1;
+
+            if current.list.is_empty() && best.is_some() {
+                break;
+            }
+        }
+
+        best
+    }
+}
+
+// Every start with a match reports its longest match, so the spans may overlap. Finding them
+// takes a pass from the right, so `find_first` and `is_match` use forward searches instead,
+// which stop early.
+impl Matcher for Regex {
+    type Matches<'a> = std::vec::IntoIter<Range<usize>>;
+
+    fn find_first(&self, text: &str) -> Option<Range<usize>> {
+        self.find(text)
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        Regex::is_match(self, text)
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a str) -> Self::Matches<'a> {
+        let chars: Vec<char> = text.chars().collect();
+        let spans: Vec<Range<usize>> = self.longest_matches(&chars).into_iter()
+            .enumerate()
+            .filter_map(|(start, end)| end.map(|end| start..end))
+            .collect();
+
+        spans.into_iter()
+    }
+}
+
+/// DFA built lazily from the NFA of a `Regex`, one state per distinct set of NFA
+/// instructions. Answers `is_match` queries in O(n) once the needed states are cached.
+pub struct LazyDfa<'r> {
+    regex: &'r Regex,
+    cache_limit: usize,
+    states: Vec<DfaState>,
+    ids: HashMap<Vec<usize>, usize>,
+    cache_flushes: usize
+}
+
+struct DfaState {
+    // Sorted NFA instructions; `AssertEnd` ones are kept until the end of the text is known.
+    instructions: Vec<usize>,
+    accepting: bool,
+    transitions: HashMap<char, usize>
+}
+
+impl<'r> LazyDfa<'r> {
+    /// Number of times the cache was flushed because it reached its limit.
+    pub fn cache_flushes(&self) -> usize {
+        self.cache_flushes
+    }
+
+    pub fn is_match(&mut self, text: &str) -> bool {
+        let restart = self.closure(&[0], false, false);
+        let mut state = self.state_id(self.closure(&[0], true, text.is_empty()));
+        for c in text.chars() {
+            if self.states[state].accepting {
+                return true;
+            }
+
+            state = match self.states[state].transitions.get(&c) {
+                Some(&next) => next,
+                None => {
+                    let mut targets: Vec<usize> = self.states[state].instructions.iter()
+                        .filter(|&&pc| self.regex.program[pc].consumes(c))
+                        .map(|&pc| pc + 1)
+                        .collect();
+                    targets.extend_from_slice(&restart);
+
+                    let instructions = self.closure(&targets, false, false);
+                    let before = self.cache_flushes;
+                    let next = self.state_id(instructions);
+                    if before == self.cache_flushes {
+                        self.states[state].transitions.insert(c, next);
+                    }
+
+                    next
+                }
+            };
+        }
+
+        if self.states[state].accepting {
+            return true;
+        }
+
+        // The end anchors hold now, so follow them.
+        let at_start = text.is_empty();
+        let instructions = self.states[state].instructions.clone();
+        self.closure(&instructions, at_start, true).iter().any(|&pc| matches!(self.regex.program[pc], Instruction::Match))
+    }
+
+    fn closure(&self, instructions: &[usize], at_start: bool, at_end: bool) -> Vec<usize> {
+        let program = &self.regex.program;
+        let mut seen = vec![false; program.len()];
+        let mut stack: Vec<usize> = instructions.to_vec();
+        let mut result = Vec::new();
+
+        while let Some(pc) = stack.pop() {
+            if seen[pc] {
+                continue;
+            }
+            seen[pc] = true;
+
+            match program[pc] {
+                Instruction::Jump(target) => stack.push(target),
+                Instruction::Split(first, second) => {
+                    stack.push(first);
+                    stack.push(second);
+                },
+                Instruction::AssertStart => {
+                    if at_start {
+                        stack.push(pc + 1);
+                    }
+                },
+                Instruction::AssertEnd if at_end => stack.push(pc + 1),
+                _ => result.push(pc)
+            }
+        }
+
+        result.sort_unstable();
+        result
+    }
+
+    fn state_id(&mut self, instructions: Vec<usize>) -> usize {
+        if let Some(&id) = self.ids.get(&instructions) {
+            return id;
+        }
+
+        if self.states.len() >= self.cache_limit {
+            self.states.clear();
+            self.ids.clear();
+            self.cache_flushes += 1;
+        }
+
+        let accepting = instructions.iter().any(|&pc| matches!(self.regex.program[pc], Instruction::Match));
+        let id = self.states.len();
+        self.ids.insert(instructions.clone(), id);
+        self.states.push(DfaState { instructions, accepting, transitions: HashMap::new() });
+
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::kmp_find;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn literal_test() {
+        let regex = Regex::new("abc").unwrap();
+        assert_eq!(vec![3, 9], regex.find_all("abbabcdefabc"));
+        assert_eq!(kmp_find(&String::from("abc"), &String::from("abbabcdefabc")), regex.find_all("abbabcdefabc"));
+
+        let regex = Regex::new("aa").unwrap();
+        assert_eq!(vec![0, 1, 2], regex.find_all("aaaa"));
+        assert_eq!(kmp_find(&String::from("aa"), &String::from("aaaa")), regex.find_all("aaaa"));
+        assert_eq!(vec![0..2, 2..4], regex.find_spans("aaaa"));
+    }
+
+    #[test]
+    fn matcher_test() {
+        let regex = Regex::new("a+b?").unwrap();
+        assert_eq!(vec![1..4, 2..4, 5..6], regex.find_iter("xaabca").collect::<Vec<_>>());
+        assert_eq!(Some(1..4), regex.find_first("xaabca"));
+        assert_eq!(3, regex.count("xaabca"));
+
+        let regex = Regex::new("x*").unwrap();
+        assert_eq!(vec![0..0, 1..3, 2..3, 3..3], regex.find_iter("axx").collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn operators_test() {
+        let regex = Regex::new("colou?r|gr(a|e)y").unwrap();
+        assert_eq!(vec![0..5, 6..12, 13..17, 18..22], regex.find_spans("color colour gray grey grr"));
+
+        let regex = Regex::new("a+b*").unwrap();
+        assert_eq!(vec![1..5, 7..8], regex.find_spans("xaabbxxa"));
+
+        let regex = Regex::new("x*").unwrap();
+        assert_eq!(vec![0..0, 1..3, 4..4], regex.find_spans("axxb"));
+    }
+
+    #[test]
+    fn leftmost_longest_test() {
+        let regex = Regex::new("bc|abcd").unwrap();
+        assert_eq!(Some(0..4), regex.find("abcd"));
+
+        let regex = Regex::new("a|ab|abc").unwrap();
+        assert_eq!(Some(1..4), regex.find("xabcx"));
+    }
+
+    #[test]
+    fn classes_and_anchors_test() {
+        let regex = Regex::new(r"^[A-Z][A-Z]-\d+$").unwrap();
+        assert!(regex.is_match("AB-1234"));
+        assert!(!regex.is_match("AB-1234 "));
+        assert!(!regex.is_match(" AB-1234"));
+
+        let regex = Regex::new("[^a-c]+").unwrap();
+        assert_eq!(vec![2..4, 5..6], regex.find_spans("abxyc-"));
+
+        let regex = Regex::new("[]-]").unwrap();
+        assert_eq!(vec![1, 3], regex.find_all("a]b-"));
+    }
+
+    #[test]
+    fn errors_test() {
+        assert_eq!(Some(RegexError::UnmatchedParenthesis(1)), Regex::new("a(b").err());
+        assert_eq!(Some(RegexError::UnmatchedParenthesis(1)), Regex::new("a)").err());
+        assert_eq!(Some(RegexError::NothingToRepeat(0)), Regex::new("*a").err());
+        assert_eq!(Some(RegexError::InvalidRange(1)), Regex::new("[z-a]").err());
+        assert_eq!(Some(RegexError::UnexpectedEnd), Regex::new("[ab").err());
+        assert_eq!(Some(RegexError::UnexpectedEnd), Regex::new("a\\").err());
+    }
+
+    #[test]
+    fn early_stop_test() {
+        // The text never ends, so only searches which stop early return.
+        let endless = |prefix: &'static str| prefix.chars().chain(std::iter::repeat('z'));
+
+        let regex = Regex::new("ab|a+").unwrap();
+        assert_eq!(Some(1..3), regex.find_at(endless("xab"), 0, false));
+        assert_eq!(Some(1..4), regex.find_at(endless("xaaa"), 0, false));
+        assert!(regex.find_at(endless("xa"), 0, true).is_some());
+
+        let regex = Regex::new("a$").unwrap();
+        assert_eq!(Some(1..2), regex.find_at("xa".chars(), 0, false));
+        assert_eq!(Some(3..4), regex.find_at("a".chars(), 3, false));
+    }
+
+    #[test]
+    fn deep_nesting_test() {
+        let depth = 100_000;
+        let pattern = format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(Some(RegexError::NestingTooDeep(Regex::MAX_NESTING)), Regex::new(&pattern).err());
+
+        let depth = Regex::MAX_NESTING;
+        let regex = Regex::new(&format!("{}a|b{}", "(".repeat(depth), ")*".repeat(depth))).unwrap();
+        assert_eq!(vec![0..3], regex.find_spans("aba"));
+
+        let regex = Regex::new(&format!("xa{}", "*+?".repeat(depth))).unwrap();
+        assert_eq!(vec![0..3, 4..5], regex.find_spans("xaa-x"));
+        let regex = Regex::new("xa+++").unwrap();
+        assert_eq!(vec![1..4], regex.find_spans("xxaa"));
+        let regex = Regex::new("xa??").unwrap();
+        assert_eq!(vec![0..1, 1..3], regex.find_spans("xxa"));
+    }
+
+    #[test]
+    fn pathological_test() {
+        // Backtracking engines take exponential time on this one.
+        let regex = Regex::new(&format!("{}{}", "a?".repeat(30), "a".repeat(30))).unwrap();
+        assert!(regex.is_match(&"a".repeat(30)));
+        assert!(!regex.is_match(&"a".repeat(29)));
+
+        // Restarting the search after every match would take quadratic time here.
+        let regex = Regex::new("a*b|a").unwrap();
+        let text = "a".repeat(100_000);
+        assert_eq!(100_000, regex.find_spans(&text).len());
+        assert_eq!(100_000, regex.find_all(&text).len());
+    }
+
+    #[test]
+    fn find_randomized_test() {
+        let patterns = ["a*b|a", "(ab|a)(c|bcd)?", "x*", "a?b+", "(a|b)*c", "[ab]c?|ca", "b|"];
+        let mut rng = StdRng::seed_from_u64(33);
+
+        for pattern in patterns {
+            let regex = Regex::new(pattern).unwrap();
+            let whole = Regex::new(&format!("^({})$", pattern)).unwrap();
+
+            for _ in 0..50 {
+                let chars: Vec<char> = (0..rng.gen_range(0..12)).map(|_| ['a', 'b', 'c', 'x'][rng.gen_range(0..4)]).collect();
+                let text: String = chars.iter().collect();
+
+                // Every start where some substring matches as a whole.
+                let starts: Vec<usize> = (0..=chars.len())
+                    .filter(|&i| (i..=chars.len()).any(|j| whole.is_match(&chars[i..j].iter().collect::<String>())))
+                    .collect();
+                assert_eq!(starts, regex.find_all(&text), "{} on {}", pattern, text);
+                assert_eq!(regex.find_iter(&text).next(), Matcher::find_first(&regex, &text));
+                assert_eq!(!starts.is_empty(), Matcher::is_match(&regex, &text));
+
+                // Searching again after every match, as the definition goes.
+                let mut spans: Vec<Range<usize>> = Vec::new();
+                let mut from = 0;
+                while let Some(span) = regex.find_at(chars[from..].iter().copied(), from, false) {
+                    from = if span.is_empty() { span.end + 1 } else { span.end };
+                    if !(span.is_empty() && spans.last().is_some_and(|last| last.end == span.start)) {
+                        spans.push(span);
+                    }
+                    if from > chars.len() {
+                        break;
+                    }
+                }
+                assert_eq!(spans, regex.find_spans(&text), "{} on {}", pattern, text);
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_dfa_test() {
+        let patterns = ["abc", "colou?r|gr(a|e)y", "^ab*$", "(a|b)*abb", "x$", "^$", "[0-9]+\\.[0-9]*"];
+        let texts = ["", "abc", "ab", "abbb", "babb", "aababb", "grey", "colr", "box", "3.", "x.5"];
+
+        for pattern in patterns {
+            let regex = Regex::new(pattern).unwrap();
+            let mut dfa = regex.lazy_dfa(100);
+            let mut tiny_dfa = regex.lazy_dfa(2);
+
+            for text in texts {
+                assert_eq!(regex.is_match(text), dfa.is_match(text), "{} on {}", pattern, text);
+                assert_eq!(regex.is_match(text), tiny_dfa.is_match(text), "{} on {}", pattern, text);
+            }
+        }
+
+        let regex = Regex::new("(a|b)*a(a|b)(a|b)(a|b)").unwrap();
+        let mut dfa = regex.lazy_dfa(4);
+        assert!(dfa.is_match("bbbbabab"));
+        assert!(dfa.cache_flushes() > 0);
+    }
+}
@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::str::CharIndices;
+use super::Matcher;
+
+/// What to do with matches overlapping each other, like "bab" at 2 and 4 in "aababab".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlap {
+    /// Take the leftmost match and skip the ones overlapping it.
+    Skip,
+    /// Merge overlapping matches into a single region.
+    Merge
+}
+
+/// Replaces all matches with `replacement`. The text is borrowed if nothing was replaced.
+pub fn replace_all<'t, M: Matcher>(matcher: &M, text: &'t str, replacement: &str, overlap: Overlap) -> Cow<'t, str> {
+    replacen(matcher, text, replacement, usize::MAX, overlap)
+}
+
+/// Replaces the first `limit` matches with `replacement`. The text is borrowed if nothing
+/// was replaced.
+pub fn replacen<'t, M: Matcher>(matcher: &M, text: &'t str, replacement: &str, limit: usize, overlap: Overlap) -> Cow<'t, str> {
+    let mut regions = Regions::new(matcher, text, overlap).take(limit).peekable();
+    if regions.peek().is_none() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for region in regions {
+        result.push_str(&text[last..region.start]);
+        result.push_str(replacement);
+        last = region.end;
+    }
+    result.push_str(&text[last..]);
+
+    Cow::Owned(result)
+}
+
+/// Splits the text on all matches. Returns slices of the text.
+pub fn split<'t, M: Matcher>(matcher: &M, text: &'t str, overlap: Overlap) -> Vec<&'t str> {
+    splitn(matcher, text, usize::MAX, overlap)
+}
+
+/// Splits the text on matches into at most `limit` pieces; the last piece holds the rest.
+pub fn splitn<'t, M: Matcher>(matcher: &M, text: &'t str, limit: usize, overlap: Overlap) -> Vec<&'t str> {
+    let mut result = Vec::new();
+    if limit == 0 {
+        return result;
+    }
+
+    let mut last = 0;
+    for region in Regions::new(matcher, text, overlap).take(limit - 1) {
+        result.push(&text[last..region.start]);
+        last = region.end;
+    }
+    result.push(&text[last..]);
+
+    result
+}
+
+// Byte ranges of the matches after applying the overlap policy.
+struct Regions<'a, M: Matcher + 'a> {
+    matches: M::Matches<'a>,
+    overlap: Overlap,
+    starts: ByteOffsets<'a>,
+    ends: ByteOffsets<'a>,
+    pending: Option<Range<usize>>
+}
+
+impl<'a, M: Matcher> Regions<'a, M> {
+    fn new(matcher: &'a M, text: &'a str, overlap: Overlap) -> Self {
+        Regions {
+            matches: matcher.find_iter(text),
+            overlap,
+            starts: ByteOffsets::new(text),
+            ends: ByteOffsets::new(text),
+            pending: None
+        }
+    }
+}
+
+impl<'a, M: Matcher> Iterator for Regions<'a, M> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        // Character ranges of the matches; the pending one is the last accepted region.
+        // An empty match right after the pending region counts as overlapping it.
+        for span in self.matches.by_ref() {
+            match self.pending.take() {
+                None => self.pending = Some(span),
+                Some(pending) if span.start < pending.end || (span.is_empty() && span.start == pending.end) => {
+                    self.pending = Some(match self.overlap {
+                        Overlap::Skip => pending,
+                        Overlap::Merge => pending.start..pending.end.max(span.end)
+                    });
+                },
+                Some(pending) => {
+                    self.pending = Some(span);
+                    return Some(self.starts.byte_offset(pending.start)..self.ends.byte_offset(pending.end));
+                }
+            }
+        }
+
+        self.pending.take().map(|pending| self.starts.byte_offset(pending.start)..self.ends.byte_offset(pending.end))
+    }
+}
+
+// Converts increasing character offsets into byte offsets.
+struct ByteOffsets<'a> {
+    chars: CharIndices<'a>,
+    length: usize,
+    position: usize,
+    offset: usize
+}
+
+impl<'a> ByteOffsets<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut chars = text.char_indices();
+        let offset = chars.next().map_or(text.len(), |(offset, _)| offset);
+
+        ByteOffsets { chars, length: text.len(), position: 0, offset }
+    }
+
+    fn byte_offset(&mut self, position: usize) -> usize {
+        while self.position < position {
+            self.offset = self.chars.next().map_or(self.length, |(offset, _)| offset);
+            self.position += 1;
+        }
+
+        self.offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::{KmpMatcher, NaiveMatcher};
+    use crate::patterns::regex::Regex;
+    use crate::patterns::unicode::{CaseFolding, MatchOptions, Normalization, UnicodeMatcher};
+
+    #[test]
+    fn replace_test() {
+        run_replace_tests(NaiveMatcher::compile);
+        run_replace_tests(KmpMatcher::compile);
+    }
+
+    #[test]
+    fn split_test() {
+        run_split_tests(NaiveMatcher::compile);
+        run_split_tests(KmpMatcher::compile);
+    }
+
+    fn run_replace_tests<M: Matcher>(compile: fn(&str) -> M) {
+        let matcher = compile("bab");
+        assert_eq!("aaXab", replace_all(&matcher, "aababab", "X", Overlap::Skip));
+        assert_eq!("aaX", replace_all(&matcher, "aababab", "X", Overlap::Merge));
+        assert_eq!("aX-X", replace_all(&matcher, "ababab-bab", "X", Overlap::Merge));
+        assert_eq!("aaXab-bab", replacen(&matcher, "aababab-bab", "X", 1, Overlap::Skip));
+
+        let matcher = compile("{name}");
+        let result = replace_all(&matcher, "Cześć {name}, {name}!", "Ola", Overlap::Skip);
+        assert_eq!("Cześć Ola, Ola!", result);
+
+        let result = replace_all(&matcher, "nothing to do", "Ola", Overlap::Skip);
+        assert!(matches!(result, Cow::Borrowed("nothing to do")));
+
+        let result = replacen(&matcher, "{name}", "Ola", 0, Overlap::Skip);
+        assert!(matches!(result, Cow::Borrowed(_)));
+    }
+
+    fn run_split_tests<M: Matcher>(compile: fn(&str) -> M) {
+        let matcher = compile(", ");
+        assert_eq!(vec!["a", "ż", "", "c"], split(&matcher, "a, ż, , c", Overlap::Skip));
+        assert_eq!(vec!["a", "ż, , c"], splitn(&matcher, "a, ż, , c", 2, Overlap::Skip));
+        assert_eq!(vec!["a, ż, , c"], splitn(&matcher, "a, ż, , c", 1, Overlap::Skip));
+        assert!(splitn(&matcher, "a, b", 0, Overlap::Skip).is_empty());
+        assert_eq!(vec![""], split(&matcher, "", Overlap::Skip));
+
+        let matcher = compile("aa");
+        assert_eq!(vec!["x", "ay"], split(&matcher, "xaaay", Overlap::Skip));
+        assert_eq!(vec!["x", "y"], split(&matcher, "xaaay", Overlap::Merge));
+        assert_eq!(vec!["", ""], split(&matcher, "aa", Overlap::Merge));
+    }
+
+    #[test]
+    fn regex_replace_test() {
+        let regex = Regex::new("a+b?").unwrap();
+        assert_eq!("xXcX", replace_all(&regex, "xaabca", "X", Overlap::Skip));
+        assert_eq!("xXcX", replace_all(&regex, "xaabca", "X", Overlap::Merge));
+        assert_eq!(vec!["x", "c", ""], split(&regex, "xaabca", Overlap::Skip));
+
+        let regex = Regex::new("ab|bcd").unwrap();
+        assert_eq!("Xcd", replace_all(&regex, "abcd", "X", Overlap::Skip));
+        assert_eq!("X", replace_all(&regex, "abcd", "X", Overlap::Merge));
+
+        let regex = Regex::new(", *").unwrap();
+        assert_eq!(vec!["a", "ż", "c"], split(&regex, "a,ż,   c", Overlap::Skip));
+
+        let regex = Regex::new("x*").unwrap();
+        assert_eq!("-a-b-", replace_all(&regex, "axxb", "-", Overlap::Skip));
+    }
+
+    #[test]
+    fn unicode_replace_test() {
+        let options = MatchOptions { case_folding: CaseFolding::Full, normalization: Normalization::Nfc };
+        let matcher = UnicodeMatcher::new("strasse", options);
+        assert_eq!("X und X", replace_all(&matcher, "Straße und STRASSE", "X", Overlap::Skip));
+        assert_eq!(vec!["", " und ", ""], split(&matcher, "Straße und STRASSE", Overlap::Skip));
+    }
+}
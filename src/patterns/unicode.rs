@@ -0,0 +1,479 @@
+mod tables;
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use super::{KmpMatcher, Matcher};
+use self::tables::{COMBINING_CLASSES, COMPOSITIONS, DECOMPOSITIONS, FULL_CASE_FOLDING, SIMPLE_CASE_FOLDING};
+
+pub use self::tables::UNICODE_VERSION;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaseFolding {
+    #[default]
+    None,
+    /// One-to-one folding, e.g. "ẞ" to "ß".
+    Simple,
+    /// Folding that may expand a character, e.g. "ß" to "ss".
+    Full
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Normalization {
+    #[default]
+    None,
+    /// Canonical composition, e.g. "e\u{301}" to "é".
+    Nfc,
+    /// Canonical decomposition, e.g. "é" to "e\u{301}".
+    Nfd
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MatchOptions {
+    pub case_folding: CaseFolding,
+    pub normalization: Normalization
+}
+
+/// Matcher comparing the pattern and the text after case folding and normalization.
+/// The Unicode tables are part of the crate (see `UNICODE_VERSION`).
+/// Matches are reported in the original text: a span is the smallest range of original
+/// characters covering the match, so it may cover a character that was only partially
+/// matched, like "ß" for "s" with full case folding.
+pub struct UnicodeMatcher {
+    options: MatchOptions,
+    matcher: KmpMatcher
+}
+
+impl UnicodeMatcher {
+    pub fn new(pattern: &str, options: MatchOptions) -> Self {
+        let (pattern, _) = transform(pattern, options);
+
+        UnicodeMatcher {
+            options,
+            matcher: KmpMatcher::compile(&pattern)
+        }
+    }
+
+    /// Finds all matches, as character ranges of the original text.
+    pub fn find_spans(&self, text: &str) -> Vec<Range<usize>> {
+        self.find_iter(text).collect()
+    }
+
+    /// Character offsets of all matches in the original text.
+    pub fn find_all(&self, text: &str) -> Vec<usize> {
+        self.find_spans(text).into_iter().map(|span| span.start).collect()
+    }
+}
+
+impl Matcher for UnicodeMatcher {
+    type Matches<'a> = UnicodeMatches<'a>;
+
+    fn find_iter<'a>(&'a self, text: &'a str) -> UnicodeMatches<'a> {
+        UnicodeMatches {
+            matcher: self,
+            rest: text,
+            rest_position: 0,
+            chars: Vec::new(),
+            sources: Vec::new(),
+            index: 0,
+            window: VecDeque::new(),
+            longest_prefix: 0,
+            last: None
+        }
+    }
+}
+
+/// Lazy iterator over the matches of a `UnicodeMatcher`. The text is transformed in chunks
+/// as the search goes, so stopping early does not transform all of it.
+pub struct UnicodeMatches<'a> {
+    matcher: &'a UnicodeMatcher,
+    // The part of the text not transformed yet and its position in characters.
+    rest: &'a str,
+    rest_position: usize,
+    // The transformed chunk and the original characters of its characters.
+    chars: Vec<char>,
+    sources: Vec<Range<usize>>,
+    index: usize,
+    // Sources of the last (at most pattern length) transformed characters.
+    window: VecDeque<Range<usize>>,
+    longest_prefix: usize,
+    last: Option<Range<usize>>
+}
+
+impl<'a> UnicodeMatches<'a> {
+    // Minimal number of original characters in a chunk.
+    const CHUNK_SIZE: usize = 1024;
+
+    // Transforms the next chunk. Chunks end before an ASCII character: it is a starter which
+    // does not compose with anything before it, so the transformations never cross the cut.
+    fn next_chunk(&mut self) -> bool {
+        if self.rest.is_empty() {
+            return false;
+        }
+
+        let mut end = self.rest.len();
+        let mut count = 0;
+        for (offset, c) in self.rest.char_indices() {
+            if count >= Self::CHUNK_SIZE && c.is_ascii() {
+                end = offset;
+                break;
+            }
+            count += 1;
+        }
+
+        let (chars, sources) = transform_chars(&self.rest[..end], self.matcher.options);
+        let shift = self.rest_position;
+        self.chars = chars;
+        self.sources = sources.into_iter().map(|source| source.start + shift..source.end + shift).collect();
+        self.index = 0;
+        self.rest = &self.rest[end..];
+        self.rest_position += count;
+        true
+    }
+}
+
+impl<'a> Iterator for UnicodeMatches<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let length = self.matcher.matcher.pattern_len();
+        if length == 0 {
+            return None;
+        }
+
+        loop {
+            if self.index == self.chars.len() && !self.next_chunk() {
+                return None;
+            }
+            if self.index == self.chars.len() {
+                continue;
+            }
+
+            let c = self.chars[self.index];
+            if self.window.len() == length {
+                self.window.pop_front();
+            }
+            self.window.push_back(self.sources[self.index].clone());
+            self.index += 1;
+
+            if self.matcher.matcher.advance(&mut self.longest_prefix, &c) {
+                let span = self.window.iter().map(|source| source.start).min().unwrap()
+                    ..self.window.iter().map(|source| source.end).max().unwrap();
+
+                // Several matches inside of a single expanded character map to the same span.
+                if self.last.as_ref() != Some(&span) {
+                    self.last = Some(span.clone());
+                    return Some(span);
+                }
+            }
+        }
+    }
+}
+
+pub fn fold_case(text: &str, folding: CaseFolding) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        fold_char(c, folding, &mut |folded| result.push(folded));
+    }
+
+    result
+}
+
+pub fn normalize(text: &str, form: Normalization) -> String {
+    let options = MatchOptions { case_folding: CaseFolding::None, normalization: form };
+    transform(text, options).0
+}
+
+fn transform(text: &str, options: MatchOptions) -> (String, Vec<Range<usize>>) {
+    let (chars, sources) = transform_chars(text, options);
+    (chars.into_iter().collect(), sources)
+}
+
+// Applies the options and returns the result together with the range of original characters
+// every resulting character comes from.
+fn transform_chars(text: &str, options: MatchOptions) -> (Vec<char>, Vec<Range<usize>>) {
+    let mut chars = Vec::with_capacity(text.len());
+    let mut sources = Vec::with_capacity(text.len());
+
+    if options.normalization == Normalization::None {
+        for (i, c) in text.chars().enumerate() {
+            fold_char(c, options.case_folding, &mut |folded| {
+                chars.push(folded);
+                sources.push(i..i + 1);
+            });
+        }
+
+        return (chars, sources);
+    }
+
+    for (i, c) in text.chars().enumerate() {
+        decompose_char(c, &mut |decomposed| {
+            chars.push(decomposed);
+            sources.push(i..i + 1);
+        });
+    }
+    reorder(&mut chars, &mut sources);
+
+    // Canonical caseless matching folds the decomposed text and decomposes it again.
+    if options.case_folding != CaseFolding::None {
+        let mut folded_chars = Vec::with_capacity(chars.len());
+        let mut folded_sources = Vec::with_capacity(sources.len());
+
+        for (c, source) in chars.into_iter().zip(sources) {
+            fold_char(c, options.case_folding, &mut |folded| decompose_char(folded, &mut |decomposed| {
+                folded_chars.push(decomposed);
+                folded_sources.push(source.clone());
+            }));
+        }
+
+        chars = folded_chars;
+        sources = folded_sources;
+        reorder(&mut chars, &mut sources);
+    }
+
+    if options.normalization == Normalization::Nfc {
+        compose(&mut chars, &mut sources);
+    }
+
+    (chars, sources)
+}
+
+fn fold_char(c: char, folding: CaseFolding, output: &mut dyn FnMut(char)) {
+    match folding {
+        CaseFolding::None => output(c),
+        CaseFolding::Simple => {
+            match SIMPLE_CASE_FOLDING.binary_search_by_key(&c, |&(key, _)| key) {
+                Ok(index) => output(SIMPLE_CASE_FOLDING[index].1),
+                Err(_) => output(c)
+            }
+        },
+        CaseFolding::Full => {
+            match FULL_CASE_FOLDING.binary_search_by_key(&c, |&(key, _)| key) {
+                Ok(index) => FULL_CASE_FOLDING[index].1.chars().for_each(output),
+                Err(_) => output(c)
+            }
+        }
+    }
+}
+
+const HANGUL_S_BASE: u32 = 0xAC00;
+const HANGUL_L_BASE: u32 = 0x1100;
+const HANGUL_V_BASE: u32 = 0x1161;
+const HANGUL_T_BASE: u32 = 0x11A7;
+const HANGUL_L_COUNT: u32 = 19;
+const HANGUL_V_COUNT: u32 = 21;
+const HANGUL_T_COUNT: u32 = 28;
+const HANGUL_N_COUNT: u32 = HANGUL_V_COUNT * HANGUL_T_COUNT;
+const HANGUL_S_COUNT: u32 = HANGUL_L_COUNT * HANGUL_N_COUNT;
+
+fn decompose_char(c: char, output: &mut dyn FnMut(char)) {
+    let code = c as u32;
+    if (HANGUL_S_BASE..HANGUL_S_BASE + HANGUL_S_COUNT).contains(&code) {
+        let index = code - HANGUL_S_BASE;
+        output(char::from_u32(HANGUL_L_BASE + index / HANGUL_N_COUNT).unwrap());
+        output(char::from_u32(HANGUL_V_BASE + (index % HANGUL_N_COUNT) / HANGUL_T_COUNT).unwrap());
+        if !index.is_multiple_of(HANGUL_T_COUNT) {
+            output(char::from_u32(HANGUL_T_BASE + index % HANGUL_T_COUNT).unwrap());
+        }
+        return;
+    }
+
+    match DECOMPOSITIONS.binary_search_by_key(&c, |&(key, _)| key) {
+        Ok(index) => DECOMPOSITIONS[index].1.chars().for_each(output),
+        Err(_) => output(c)
+    }
+}
+
+fn combining_class(c: char) -> u8 {
+    match COMBINING_CLASSES.binary_search_by_key(&c, |&(key, _)| key) {
+        Ok(index) => COMBINING_CLASSES[index].1,
+        Err(_) => 0
+    }
+}
+
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let (first_code, second_code) = (first as u32, second as u32);
+
+    if (HANGUL_L_BASE..HANGUL_L_BASE + HANGUL_L_COUNT).contains(&first_code)
+        && (HANGUL_V_BASE..HANGUL_V_BASE + HANGUL_V_COUNT).contains(&second_code) {
+        let index = (first_code - HANGUL_L_BASE) * HANGUL_N_COUNT + (second_code - HANGUL_V_BASE) * HANGUL_T_COUNT;
+        return char::from_u32(HANGUL_S_BASE + index);
+    }
+
+    if (HANGUL_S_BASE..HANGUL_S_BASE + HANGUL_S_COUNT).contains(&first_code)
+        && (first_code - HANGUL_S_BASE).is_multiple_of(HANGUL_T_COUNT)
+        && (HANGUL_T_BASE + 1..HANGUL_T_BASE + HANGUL_T_COUNT).contains(&second_code) {
+        return char::from_u32(first_code + second_code - HANGUL_T_BASE);
+    }
+
+    COMPOSITIONS.binary_search_by_key(&(first, second), |&(pair, _)| pair)
+        .ok()
+        .map(|index| COMPOSITIONS[index].1)
+}
+
+// Canonical ordering: stable sort of every run of non-starters by combining class.
+fn reorder(chars: &mut [char], sources: &mut [Range<usize>]) {
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+
+        let mut end = start;
+        while end < chars.len() && combining_class(chars[end]) != 0 {
+            end += 1;
+        }
+
+        let mut run: Vec<(char, Range<usize>)> = chars[start..end].iter().copied().zip(sources[start..end].iter().cloned()).collect();
+        run.sort_by_key(|(c, _)| combining_class(*c));
+        for (i, (c, source)) in run.into_iter().enumerate() {
+            chars[start + i] = c;
+            sources[start + i] = source;
+        }
+
+        start = end;
+    }
+}
+
+// Canonical composition of a decomposed and reordered sequence.
+fn compose(chars: &mut Vec<char>, sources: &mut Vec<Range<usize>>) {
+    let mut result_chars: Vec<char> = Vec::with_capacity(chars.len());
+    let mut result_sources: Vec<Range<usize>> = Vec::with_capacity(chars.len());
+    let mut starter: Option<usize> = None;
+    let mut last_class = 0;
+
+    for (c, source) in chars.drain(..).zip(sources.drain(..)) {
+        let class = combining_class(c);
+
+        if let Some(starter_index) = starter {
+            // A character is blocked from the starter by anything in between with the same
+            // or higher combining class, or by another starter.
+            let adjacent = starter_index + 1 == result_chars.len();
+            let blocked = !adjacent && (last_class == 0 || last_class >= class);
+
+            if !blocked {
+                if let Some(composed) = compose_pair(result_chars[starter_index], c) {
+                    result_chars[starter_index] = composed;
+                    let merged = &mut result_sources[starter_index];
+                    *merged = merged.start.min(source.start)..merged.end.max(source.end);
+                    continue;
+                }
+            }
+        }
+
+        if class == 0 {
+            starter = Some(result_chars.len());
+        }
+        last_class = class;
+        result_chars.push(c);
+        result_sources.push(source);
+    }
+
+    *chars = result_chars;
+    *sources = result_sources;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn normalize_test() {
+        assert_eq!("e\u{301}", normalize("é", Normalization::Nfd));
+        assert_eq!("é", normalize("e\u{301}", Normalization::Nfc));
+        assert_eq!("\u{1ead}", normalize("a\u{323}\u{302}", Normalization::Nfc));
+        assert_eq!("\u{1ead}", normalize("a\u{302}\u{323}", Normalization::Nfc));
+        assert_eq!("a\u{323}\u{302}", normalize("a\u{302}\u{323}", Normalization::Nfd));
+        assert_eq!("\u{1112}\u{1161}\u{11ab}", normalize("한", Normalization::Nfd));
+        assert_eq!("한", normalize("\u{1112}\u{1161}\u{11ab}", Normalization::Nfc));
+        // Composition exclusions and singletons stay decomposed.
+        assert_eq!("\u{915}\u{93c}", normalize("\u{958}", Normalization::Nfc));
+        assert_eq!("\u{3a9}", normalize("\u{2126}", Normalization::Nfc));
+    }
+
+    #[test]
+    fn fold_case_test() {
+        assert_eq!("strasse", fold_case("Straße", CaseFolding::Full));
+        assert_eq!("straße", fold_case("STRAẞE", CaseFolding::Simple));
+        assert_eq!("σασ", fold_case("ΣΑς", CaseFolding::Simple));
+    }
+
+    #[test]
+    fn case_insensitive_find_test() {
+        let options = MatchOptions { case_folding: CaseFolding::Full, normalization: Normalization::None };
+        let matcher = UnicodeMatcher::new("STRASSE", options);
+        assert_eq!(vec![0..6, 11..18], matcher.find_spans("Straße und STRASSE"));
+
+        let options = MatchOptions { case_folding: CaseFolding::Simple, normalization: Normalization::None };
+        let matcher = UnicodeMatcher::new("STRASSE", options);
+        assert_eq!(vec![11], matcher.find_all("Straße und strasse"));
+
+        let options = MatchOptions { case_folding: CaseFolding::Full, normalization: Normalization::None };
+        assert_eq!(vec![0..1], UnicodeMatcher::new("s", options).find_spans("ß"));
+    }
+
+    #[test]
+    fn normalized_find_test() {
+        let text = "cafe\u{301} café CAFÉ";
+        for normalization in [Normalization::Nfc, Normalization::Nfd] {
+            let options = MatchOptions { case_folding: CaseFolding::None, normalization };
+            assert_eq!(vec![0..5, 6..10], UnicodeMatcher::new("café", options).find_spans(text));
+            assert_eq!(vec![0..5, 6..10], UnicodeMatcher::new("cafe\u{301}", options).find_spans(text));
+
+            let options = MatchOptions { case_folding: CaseFolding::Full, normalization };
+            assert_eq!(vec![0, 6, 11], UnicodeMatcher::new("Café", options).find_all(text));
+        }
+
+        assert_eq!(Vec::<usize>::new(), UnicodeMatcher::new("café", MatchOptions::default()).find_all("cafe\u{301}"));
+    }
+
+    #[test]
+    fn chunked_find_test() {
+        let mut rng = StdRng::seed_from_u64(35);
+        let alphabet = ['a', 'e', 'E', 's', 'S', 'ß', 'é', 'É', '\u{301}', '\u{323}', ' '];
+        let options = [
+            MatchOptions { case_folding: CaseFolding::Full, normalization: Normalization::Nfc },
+            MatchOptions { case_folding: CaseFolding::Simple, normalization: Normalization::Nfd },
+            MatchOptions { case_folding: CaseFolding::None, normalization: Normalization::None }
+        ];
+
+        for _ in 0..20 {
+            let text: String = (0..rng.gen_range(0..5000)).map(|_| alphabet[rng.gen_range(0..alphabet.len())]).collect();
+            let pattern: String = (0..rng.gen_range(1..4)).map(|_| alphabet[rng.gen_range(0..alphabet.len())]).collect();
+
+            for options in options {
+                let matcher = UnicodeMatcher::new(&pattern, options);
+
+                // Reference: transform the whole text at once.
+                let (transformed, sources) = transform(&text, options);
+                let mut expected: Vec<Range<usize>> = Vec::new();
+                for matched in matcher.matcher.find_iter(&transformed) {
+                    let covered = &sources[matched];
+                    let span = covered.iter().map(|source| source.start).min().unwrap()
+                        ..covered.iter().map(|source| source.end).max().unwrap();
+                    if expected.last() != Some(&span) {
+                        expected.push(span);
+                    }
+                }
+
+                assert_eq!(expected, matcher.find_spans(&text));
+            }
+        }
+    }
+
+    #[test]
+    fn early_stop_test() {
+        let text = format!("STRASSE{}", " und Straße".repeat(10_000));
+        let options = MatchOptions { case_folding: CaseFolding::Full, normalization: Normalization::Nfc };
+        let matcher = UnicodeMatcher::new("straße", options);
+
+        let mut matches = matcher.find_iter(&text);
+        assert_eq!(Some(0..7), matches.next());
+        assert!(matches.rest_position < 2 * UnicodeMatches::CHUNK_SIZE);
+        assert_eq!(Some(0..7), matcher.find_first(&text));
+        assert!(matcher.is_match(&text));
+        assert_eq!(10_001, matcher.find_iter(&text).count());
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Suffix array of a static text, together with the LCP array. Unlike the one-shot matchers
+/// in `patterns`, the text is indexed once and can then be queried many times.
+/// All offsets are positions in the indexed sequence (characters for `from_text`).
+pub struct SuffixArray<T> {
+    text: Vec<T>,
+    suffixes: Vec<usize>,
+    lcp: Vec<usize>
+}
+
+/// A substring common to several sequences: its length and the offset in every sequence.
+#[derive(Debug, PartialEq)]
+pub struct CommonSubstring {
+    pub length: usize,
+    pub offsets: Vec<usize>
+}
+
+impl CommonSubstring {
+    /// The common substring itself, taken from the given sequence (one of the inputs, in order).
+    pub fn substring<'a, T>(&self, sequences: &[&'a [T]], index: usize) -> &'a [T] {
+        &sequences[index][self.offsets[index]..self.offsets[index] + self.length]
+    }
+}
+
+impl SuffixArray<char> {
+    pub fn from_text(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        SuffixArray::new(&chars)
+    }
+}
+
+impl<T: Ord + Clone> SuffixArray<T> {
+    pub fn new(text: &[T]) -> Self {
+        let suffixes = build_suffix_array(text);
+        let lcp = build_lcp_array(text, &suffixes);
+
+        SuffixArray { text: text.to_vec(), suffixes, lcp }
+    }
+
+    pub fn text(&self) -> &[T] {
+        &self.text
+    }
+
+    /// Starting positions of the suffixes in lexicographic order.
+    pub fn suffixes(&self) -> &[usize] {
+        &self.suffixes
+    }
+
+    /// `lcp[i]` is the length of the longest common prefix of suffixes `i - 1` and `i`
+    /// (in the sorted order); `lcp[0]` is 0.
+    pub fn lcp(&self) -> &[usize] {
+        &self.lcp
+    }
+
+    /// Range of indices in `suffixes()` of the suffixes starting with the pattern.
+    pub fn suffix_range(&self, pattern: &[T]) -> Range<usize> {
+        let prefix = |start: usize| &self.text[start..(start + pattern.len()).min(self.text.len())];
+
+        let begin = self.suffixes.partition_point(|&start| prefix(start) < pattern);
+        let end = begin + self.suffixes[begin..].partition_point(|&start| prefix(start) <= pattern);
+
+        begin..end
+    }
+
+    /// Finds all occurrences of a pattern using binary search. The result is sorted.
+    pub fn find_all(&self, pattern: &[T]) -> Vec<usize> {
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+
+        let mut result = self.suffixes[self.suffix_range(pattern)].to_vec();
+        result.sort_unstable();
+        result
+    }
+
+    /// Counts (possibly overlapping) occurrences of the pattern; the empty pattern has none.
+    pub fn count(&self, pattern: &[T]) -> usize {
+        if pattern.is_empty() {
+            return 0;
+        }
+
+        self.suffix_range(pattern).len()
+    }
+
+    /// The longest substring occurring at least twice (occurrences may overlap).
+    /// Returns the range of its first occurrence in the sorted order, or `None` if no
+    /// character repeats.
+    pub fn longest_repeated_substring(&self) -> Option<Range<usize>> {
+        let (index, &length) = self.lcp.iter().enumerate().max_by_key(|&(index, length)| (length, std::cmp::Reverse(index)))?;
+        if length == 0 {
+            return None;
+        }
+
+        let start = self.suffixes[index];
+        Some(start..start + length)
+    }
+
+    /// Number of distinct non-empty substrings of the text.
+    pub fn distinct_substrings(&self) -> usize {
+        let n = self.text.len();
+        n * (n + 1) / 2 - self.lcp.iter().sum::<usize>()
+    }
+}
+
+/// Finds the longest contiguous substring common to both sequences, using a suffix array of
+/// their concatenation. `dynamic::longest_contiguous_common_substring` is the O(nm) dynamic
+/// programming version; `dynamic::longest_common_substring` computes a subsequence instead.
+pub fn longest_common_substring<T: Ord + Clone>(first: &[T], second: &[T]) -> Option<CommonSubstring> {
+    // `None` separates the sequences, so no common prefix can cross the boundary.
+    let joined: Vec<Option<T>> = first.iter().cloned().map(Some)
+        .chain(std::iter::once(None))
+        .chain(second.iter().cloned().map(Some))
+        .collect();
+
+    let suffix_array = SuffixArray::new(&joined);
+    let suffixes = suffix_array.suffixes();
+    let mut best: Option<CommonSubstring> = None;
+
+    for i in 1..suffixes.len() {
+        let (left, right) = (suffixes[i - 1], suffixes[i]);
+        let length = suffix_array.lcp()[i];
+
+        if length == 0 || (left < first.len()) == (right < first.len()) {
+            continue;
+        }
+
+        if best.as_ref().is_none_or(|best| length > best.length) {
+            let (in_first, in_second) = if left < first.len() { (left, right) } else { (right, left) };
+            best = Some(CommonSubstring { length, offsets: vec![in_first, in_second - first.len() - 1] });
+        }
+    }
+
+    best
+}
+
+/// Finds the longest contiguous substring common to all sequences, using a generalized suffix
+/// array: a window sliding over the sorted suffixes, which contains a suffix of every sequence,
+/// with the minimum of the LCP values inside tracked by a monotonic queue. O(N log N) for the
+/// total length N.
+pub fn longest_common_substring_of_all<T: Ord + Clone>(sequences: &[&[T]]) -> Option<CommonSubstring> {
+    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+    enum Element<T> {
+        Value(T),
+        // Distinct separators, so no common prefix can cross a sequence boundary.
+        Separator(usize)
+    }
+
+    let k = sequences.len();
+    if k == 0 || sequences.iter().any(|sequence| sequence.is_empty()) {
+        return None;
+    }
+    if k == 1 {
+        return Some(CommonSubstring { length: sequences[0].len(), offsets: vec![0] });
+    }
+
+    let mut joined = Vec::new();
+    let mut owners = Vec::new();
+    let mut starts = Vec::with_capacity(k);
+    for (index, sequence) in sequences.iter().enumerate() {
+        starts.push(joined.len());
+        joined.extend(sequence.iter().cloned().map(Element::Value));
+        owners.extend(std::iter::repeat_n(Some(index), sequence.len()));
+        joined.push(Element::Separator(index));
+        owners.push(None);
+    }
+
+    let suffix_array = SuffixArray::new(&joined);
+    let suffixes = suffix_array.suffixes();
+    let lcp = suffix_array.lcp();
+
+    let mut counts = vec![0; k];
+    let mut covered = 0;
+    let mut left = 0;
+    // Indices i in (left, right] with increasing lcp[i]; the front is the window minimum.
+    let mut minimums = VecDeque::new();
+    // Length of the best substring and the window where it was found.
+    let mut best: Option<(usize, Range<usize>)> = None;
+
+    for right in 0..suffixes.len() {
+        if let Some(owner) = owners[suffixes[right]] {
+            counts[owner] += 1;
+            if counts[owner] == 1 {
+                covered += 1;
+            }
+        }
+
+        if right > left {
+            while minimums.back().is_some_and(|&i| lcp[i] >= lcp[right]) {
+                minimums.pop_back();
+            }
+            minimums.push_back(right);
+        }
+
+        while covered == k {
+            let length = minimums.front().map_or(0, |&i| lcp[i]);
+            if length > 0 && best.as_ref().is_none_or(|(best, _)| length > *best) {
+                best = Some((length, left..right + 1));
+            }
+
+            if let Some(owner) = owners[suffixes[left]] {
+                counts[owner] -= 1;
+                if counts[owner] == 0 {
+                    covered -= 1;
+                }
+            }
+
+            left += 1;
+            while minimums.front().is_some_and(|&i| i <= left) {
+                minimums.pop_front();
+            }
+        }
+    }
+
+    // Every suffix in the window starts with the substring.
+    let (length, window) = best?;
+    let mut offsets = vec![0; k];
+    for &position in &suffixes[window] {
+        if let Some(owner) = owners[position] {
+            offsets[owner] = position - starts[owner];
+        }
+    }
+
+    Some(CommonSubstring { length, offsets })
+}
+
+// Prefix doubling: after each round suffixes are sorted by their first 2k elements.
+// Every round is a linear radix sort, so the total time is O(n log n).
+// Used directly by indexes that need only the suffix order, without a copy of the text or LCP.
+pub(crate) fn build_suffix_array<T: Ord>(text: &[T]) -> Vec<usize> {
+    let n = text.len();
+    let mut suffixes: Vec<usize> = (0..n).collect();
+    suffixes.sort_by(|&a, &b| text[a].cmp(&text[b]));
+
+    let mut ranks = vec![0; n];
+    for i in 1..n {
+        ranks[suffixes[i]] = ranks[suffixes[i - 1]] + usize::from(text[suffixes[i]] != text[suffixes[i - 1]]);
+    }
+
+    let mut by_second_key = vec![0; n];
+    let mut counts = vec![0; n + 1];
+    let mut new_ranks = vec![0; n];
+    let mut k = 1;
+
+    while k < n && ranks[suffixes[n - 1]] < n - 1 {
+        // Suffixes shorter than k have an empty second half, which sorts first.
+        by_second_key.clear();
+        by_second_key.extend(n - k..n);
+        by_second_key.extend(suffixes.iter().filter(|&&start| start >= k).map(|&start| start - k));
+
+        // Stable counting sort by the rank of the first half.
+        counts.iter_mut().for_each(|count| *count = 0);
+        for &start in &by_second_key {
+            counts[ranks[start] + 1] += 1;
+        }
+        for i in 1..=n {
+            counts[i] += counts[i - 1];
+        }
+        for &start in &by_second_key {
+            suffixes[counts[ranks[start]]] = start;
+            counts[ranks[start]] += 1;
+        }
+
+        let key = |start: usize| (ranks[start], if start + k < n { Some(ranks[start + k]) } else { None });
+        new_ranks[suffixes[0]] = 0;
+        for i in 1..n {
+            new_ranks[suffixes[i]] = new_ranks[suffixes[i - 1]] + usize::from(key(suffixes[i]) != key(suffixes[i - 1]));
+        }
+
+        std::mem::swap(&mut ranks, &mut new_ranks);
+        k *= 2;
+    }
+
+    suffixes
+}
+
+// Kasai's algorithm: the LCP of consecutive text positions drops by at most one.
+fn build_lcp_array<T: Ord>(text: &[T], suffixes: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut ranks = vec![0; n];
+    for (rank, &start) in suffixes.iter().enumerate() {
+        ranks[start] = rank;
+    }
+
+    let mut lcp = vec![0; n];
+    let mut length: usize = 0;
+
+    for start in 0..n {
+        if ranks[start] == 0 {
+            length = 0;
+            continue;
+        }
+
+        let previous = suffixes[ranks[start] - 1];
+        while start + length < n && previous + length < n && text[start + length] == text[previous + length] {
+            length += 1;
+        }
+
+        lcp[ranks[start]] = length;
+        length = length.saturating_sub(1);
+    }
+
+    lcp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::naive_find;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn banana_test() {
+        let suffix_array = SuffixArray::from_text("banana");
+
+        assert_eq!(&[5, 3, 1, 0, 4, 2], suffix_array.suffixes());
+        assert_eq!(&[0, 1, 3, 0, 0, 2], suffix_array.lcp());
+        assert_eq!(vec![1, 3], suffix_array.find_all(&['a', 'n', 'a']));
+        assert_eq!(0, suffix_array.count(&['n', 'b']));
+        assert_eq!(Some(1..4), suffix_array.longest_repeated_substring());
+        assert_eq!(15, suffix_array.distinct_substrings());
+    }
+
+    #[test]
+    fn empty_text_test() {
+        let suffix_array = SuffixArray::from_text("");
+
+        assert!(suffix_array.suffixes().is_empty());
+        assert_eq!(Vec::<usize>::new(), suffix_array.find_all(&['a']));
+        assert_eq!(None, suffix_array.longest_repeated_substring());
+        assert_eq!(0, suffix_array.distinct_substrings());
+
+        let suffix_array = SuffixArray::from_text("ab");
+        assert_eq!(0, suffix_array.count(&[]));
+        assert!(suffix_array.find_all(&[]).is_empty());
+    }
+
+    #[test]
+    fn randomized_test() {
+        let mut rng = StdRng::seed_from_u64(29);
+
+        for _ in 0..200 {
+            let length = rng.gen_range(1..60);
+            let text: String = (0..length).map(|_| rng.gen_range('a'..'d')).collect();
+            let chars: Vec<char> = text.chars().collect();
+            let suffix_array = SuffixArray::from_text(&text);
+
+            let mut expected: Vec<usize> = (0..length).collect();
+            expected.sort_by(|&a, &b| chars[a..].cmp(&chars[b..]));
+            assert_eq!(&expected[..], suffix_array.suffixes());
+
+            let pattern: String = (0..rng.gen_range(1..4)).map(|_| rng.gen_range('a'..'d')).collect();
+            let pattern_chars: Vec<char> = pattern.chars().collect();
+            assert_eq!(naive_find(&pattern, &text), suffix_array.find_all(&pattern_chars));
+
+            let substrings: HashSet<&[char]> = (0..length).flat_map(|i| (i + 1..=length).map(move |j| (i, j)))
+                .map(|(i, j)| &chars[i..j])
+                .collect();
+            assert_eq!(substrings.len(), suffix_array.distinct_substrings());
+        }
+    }
+
+    #[test]
+    fn longest_common_substring_test() {
+        let result = longest_common_substring(b"xabcdy", b"zzbcdw").unwrap();
+        assert_eq!(CommonSubstring { length: 3, offsets: vec![2, 2] }, result);
+
+        assert_eq!(None, longest_common_substring(b"abc", b"xyz"));
+    }
+
+    #[test]
+    fn longest_common_substring_of_all_test() {
+        let sequences: Vec<&[u8]> = vec![b"xabcdey", b"bcdezzabc", b"qqabcde"];
+        let result = longest_common_substring_of_all(&sequences).unwrap();
+
+        assert_eq!(CommonSubstring { length: 4, offsets: vec![2, 0, 3] }, result);
+        assert_eq!(b"bcde", result.substring(&sequences, 1));
+
+        let sequences: Vec<&[u8]> = vec![b"abc", b"abd", b"xbz"];
+        assert_eq!(b"b", longest_common_substring_of_all(&sequences).unwrap().substring(&sequences, 2));
+        assert_eq!(None, longest_common_substring_of_all::<u8>(&[b"abc", b"xyz"]));
+        assert_eq!(None, longest_common_substring_of_all::<u8>(&[b"abc", b""]));
+        assert_eq!(None, longest_common_substring_of_all::<u8>(&[]));
+    }
+
+    #[test]
+    fn longest_common_substring_of_all_randomized_test() {
+        let mut rng = StdRng::seed_from_u64(40);
+
+        for _ in 0..100 {
+            let first: Vec<u8> = (0..rng.gen_range(1..30)).map(|_| rng.gen_range(b'a'..b'c')).collect();
+            let second: Vec<u8> = (0..rng.gen_range(1..30)).map(|_| rng.gen_range(b'a'..b'c')).collect();
+
+            let expected = longest_common_substring(&first, &second).map_or(0, |result| result.length);
+            let result = longest_common_substring_of_all(&[&first, &second]);
+            assert_eq!(expected, result.as_ref().map_or(0, |result| result.length));
+
+            if let Some(result) = result {
+                let sequences: Vec<&[u8]> = vec![&first, &second];
+                assert_eq!(result.substring(&sequences, 0), result.substring(&sequences, 1));
+            }
+        }
+    }
+}
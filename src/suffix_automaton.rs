@@ -0,0 +1,344 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// Suffix automaton: the smallest automaton accepting all suffixes of a text. The text can
+/// grow one element at a time (amortized O(log σ) per element), which makes it suitable for
+/// indexing buffers that are still being written. `contains`, `find_first` and
+/// `distinct_substrings` stay cheap while the text grows; `count` and `kth_substring` rebuild
+/// an O(n) table on their first call after the text has changed, so grow the text in batches
+/// with `extend` when these are queried often.
+pub struct SuffixAutomaton<T> {
+    states: Vec<State<T>>,
+    last: usize,
+    length: usize,
+    distinct_substrings: usize,
+    // Derived data, recomputed on demand after the text has changed.
+    occurrences: RefCell<Option<Vec<usize>>>,
+    paths: RefCell<Option<Vec<usize>>>
+}
+
+struct State<T> {
+    // Length of the longest substring in the equivalence class.
+    length: usize,
+    link: Option<usize>,
+    next: BTreeMap<T, usize>,
+    // Position of the last element of the first occurrence.
+    first_end: usize,
+    is_clone: bool
+}
+
+impl SuffixAutomaton<char> {
+    pub fn from_text(text: &str) -> Self {
+        let mut automaton = SuffixAutomaton::new();
+        automaton.extend(text.chars());
+
+        automaton
+    }
+}
+
+impl<T: Ord + Clone> Default for SuffixAutomaton<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> SuffixAutomaton<T> {
+    pub fn new() -> Self {
+        let root = State { length: 0, link: None, next: BTreeMap::new(), first_end: 0, is_clone: false };
+
+        SuffixAutomaton {
+            states: vec![root],
+            last: 0,
+            length: 0,
+            distinct_substrings: 0,
+            occurrences: RefCell::new(None),
+            paths: RefCell::new(None)
+        }
+    }
+
+    /// Length of the indexed text.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Appends a single element to the indexed text.
+    pub fn push(&mut self, value: T) {
+        let current = self.states.len();
+        self.states.push(State {
+            length: self.length + 1,
+            link: None,
+            next: BTreeMap::new(),
+            first_end: self.length,
+            is_clone: false
+        });
+
+        let mut state = Some(self.last);
+        while let Some(p) = state {
+            if self.states[p].next.contains_key(&value) {
+                break;
+            }
+
+            self.states[p].next.insert(value.clone(), current);
+            state = self.states[p].link;
+        }
+
+        match state {
+            None => {
+                self.states[current].link = Some(0);
+            },
+            Some(p) => {
+                let q = self.states[p].next[&value];
+                if self.states[p].length + 1 == self.states[q].length {
+                    self.states[current].link = Some(q);
+                }
+                else {
+                    // Split the class of `q`, so that lengths along the suffix links stay consistent.
+                    let clone = self.states.len();
+                    self.states.push(State {
+                        length: self.states[p].length + 1,
+                        link: self.states[q].link,
+                        next: self.states[q].next.clone(),
+                        first_end: self.states[q].first_end,
+                        is_clone: true
+                    });
+
+                    let mut state = Some(p);
+                    while let Some(p) = state {
+                        if self.states[p].next.get(&value) != Some(&q) {
+                            break;
+                        }
+
+                        self.states[p].next.insert(value.clone(), clone);
+                        state = self.states[p].link;
+                    }
+
+                    self.states[q].link = Some(clone);
+                    self.states[current].link = Some(clone);
+                }
+            }
+        }
+
+        // The new substrings are the suffixes of the text longer than the one the link stands for.
+        let link = self.states[current].link.unwrap();
+        self.distinct_substrings += self.states[current].length - self.states[link].length;
+        self.last = current;
+        self.length += 1;
+        self.occurrences.replace(None);
+        self.paths.replace(None);
+    }
+
+    /// Appends all elements to the indexed text.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        for value in values {
+            self.push(value);
+        }
+    }
+
+    /// Checks if the pattern is a substring of the text. Like in `SuffixArray` and `FmIndex`,
+    /// the empty pattern has no occurrences.
+    pub fn contains(&self, pattern: &[T]) -> bool {
+        !pattern.is_empty() && self.walk(pattern).is_some()
+    }
+
+    /// Counts (possibly overlapping) occurrences of a non-empty pattern.
+    pub fn count(&self, pattern: &[T]) -> usize {
+        if pattern.is_empty() {
+            return 0;
+        }
+
+        match self.walk(pattern) {
+            Some(state) => self.with_occurrences(|occurrences| occurrences[state]),
+            None => 0
+        }
+    }
+
+    /// Finds the position of the first occurrence of a non-empty pattern.
+    pub fn find_first(&self, pattern: &[T]) -> Option<usize> {
+        if pattern.is_empty() {
+            return None;
+        }
+
+        self.walk(pattern).map(|state| self.states[state].first_end + 1 - pattern.len())
+    }
+
+    /// Number of distinct non-empty substrings of the text.
+    pub fn distinct_substrings(&self) -> usize {
+        self.distinct_substrings
+    }
+
+    /// Returns the k-th (counting from 0) distinct non-empty substring in lexicographic order.
+    pub fn kth_substring(&self, k: usize) -> Option<Vec<T>> {
+        self.with_paths(|paths| {
+            if k + 1 >= paths[0] {
+                return None;
+            }
+
+            let mut result = Vec::new();
+            let mut remaining = k;
+            let mut state = 0;
+
+            loop {
+                for (value, &next) in &self.states[state].next {
+                    if remaining < paths[next] {
+                        result.push(value.clone());
+                        if remaining == 0 {
+                            return Some(result);
+                        }
+
+                        remaining -= 1;
+                        state = next;
+                        break;
+                    }
+
+                    remaining -= paths[next];
+                }
+            }
+        })
+    }
+
+    fn walk(&self, pattern: &[T]) -> Option<usize> {
+        let mut state = 0;
+        for value in pattern {
+            state = *self.states[state].next.get(value)?;
+        }
+
+        Some(state)
+    }
+
+    // States sorted by decreasing length, which is a reverse topological order of both
+    // the transitions and the suffix links.
+    fn states_by_length(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.length + 2];
+        for state in &self.states {
+            counts[state.length + 1] += 1;
+        }
+        for i in 1..counts.len() {
+            counts[i] += counts[i - 1];
+        }
+
+        let mut result = vec![0; self.states.len()];
+        for (index, state) in self.states.iter().enumerate() {
+            result[counts[state.length]] = index;
+            counts[state.length] += 1;
+        }
+
+        result.reverse();
+        result
+    }
+
+    fn with_occurrences<R, F: FnOnce(&[usize]) -> R>(&self, f: F) -> R {
+        let mut cache = self.occurrences.borrow_mut();
+        let occurrences = cache.get_or_insert_with(|| {
+            let mut occurrences: Vec<usize> = self.states.iter().map(|state| usize::from(!state.is_clone)).collect();
+            occurrences[0] = 0;
+
+            for state in self.states_by_length() {
+                if let Some(link) = self.states[state].link {
+                    occurrences[link] += occurrences[state];
+                }
+            }
+
+            occurrences
+        });
+
+        f(occurrences)
+    }
+
+    fn with_paths<R, F: FnOnce(&[usize]) -> R>(&self, f: F) -> R {
+        let mut cache = self.paths.borrow_mut();
+        let paths = cache.get_or_insert_with(|| {
+            let mut paths = vec![1; self.states.len()];
+
+            for state in self.states_by_length() {
+                for &next in self.states[state].next.values() {
+                    paths[state] += paths[next];
+                }
+            }
+
+            paths
+        });
+
+        f(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::fm_index::FmIndex;
+    use crate::patterns::kmp_find;
+    use crate::suffix_array::SuffixArray;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn abcbc_test() {
+        let automaton = SuffixAutomaton::from_text("abcbc");
+
+        assert!(automaton.contains(&['b', 'c', 'b']));
+        assert!(!automaton.contains(&['c', 'a']));
+        assert_eq!(2, automaton.count(&['b', 'c']));
+        assert_eq!(Some(1), automaton.find_first(&['b', 'c']));
+        assert_eq!(None, automaton.find_first(&['b', 'b']));
+        assert_eq!(12, automaton.distinct_substrings());
+        assert_eq!(Some(vec!['a']), automaton.kth_substring(0));
+        assert_eq!(Some(vec!['a', 'b']), automaton.kth_substring(1));
+        assert_eq!(Some(vec!['c', 'b', 'c']), automaton.kth_substring(11));
+        assert_eq!(None, automaton.kth_substring(12));
+    }
+
+    #[test]
+    fn empty_pattern_test() {
+        // The same convention as in the other text indexes.
+        let mut automaton = SuffixAutomaton::new();
+        assert!(!automaton.contains(&[]));
+        assert_eq!(0, automaton.count(&[]));
+        assert_eq!(None, automaton.find_first(&[]));
+
+        automaton.extend("abab".chars());
+        let text: Vec<char> = "abab".chars().collect();
+        assert!(!automaton.contains(&[]));
+        assert_eq!(0, automaton.count(&[]));
+        assert_eq!(None, automaton.find_first(&[]));
+        assert_eq!(SuffixArray::new(&text).count(&[]), automaton.count(&[]));
+        assert_eq!(FmIndex::new(b"abab").count(b""), automaton.count(&[]));
+        assert_eq!(7, automaton.distinct_substrings());
+        assert_eq!(2, automaton.count(&['a', 'b']));
+    }
+
+    #[test]
+    fn incremental_test() {
+        let mut rng = StdRng::seed_from_u64(30);
+        let mut automaton = SuffixAutomaton::new();
+        let mut text = String::new();
+
+        for _ in 0..150 {
+            let c = rng.gen_range('a'..'d');
+            automaton.push(c);
+            text.push(c);
+
+            let pattern: String = (0..rng.gen_range(1..4)).map(|_| rng.gen_range('a'..'d')).collect();
+            let pattern_chars: Vec<char> = pattern.chars().collect();
+            let expected = kmp_find(&pattern, &text);
+
+            assert_eq!(expected.len(), automaton.count(&pattern_chars));
+            assert_eq!(expected.first().copied(), automaton.find_first(&pattern_chars));
+            assert_eq!(SuffixArray::from_text(&text).distinct_substrings(), automaton.distinct_substrings());
+        }
+
+        let chars: Vec<char> = text.chars().take(20).collect();
+        let automaton = SuffixAutomaton::from_text(&chars.iter().collect::<String>());
+        let substrings: BTreeSet<&[char]> = (0..chars.len()).flat_map(|i| (i + 1..=chars.len()).map(move |j| (i, j)))
+            .map(|(i, j)| &chars[i..j])
+            .collect();
+
+        for (k, substring) in substrings.into_iter().enumerate() {
+            assert_eq!(Some(substring.to_vec()), automaton.kth_substring(k));
+        }
+    }
+}
@@ -0,0 +1,644 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::collections::btree_map::Entry;
+
+/// Map keyed by byte sequences (both `&str` and `&[u8]` keys are accepted), storing one node
+/// per key byte. Children are kept in order, so iteration is lexicographic.
+pub struct Trie<V> {
+    root: TrieNode<V>,
+    len: usize
+}
+
+struct TrieNode<V> {
+    value: Option<V>,
+    children: BTreeMap<u8, TrieNode<V>>
+}
+
+impl<V> TrieNode<V> {
+    fn new() -> Self {
+        TrieNode { value: None, children: BTreeMap::new() }
+    }
+}
+
+/// Path-compressed variant of `Trie`: every edge holds a whole run of bytes, so chains of
+/// nodes with a single child are stored as one node.
+pub struct RadixTree<V> {
+    root: RadixNode<V>,
+    len: usize
+}
+
+struct RadixNode<V> {
+    // Bytes on the edge leading to this node; empty only for the root.
+    label: Vec<u8>,
+    value: Option<V>,
+    // Children keyed by the first byte of their label.
+    children: BTreeMap<u8, RadixNode<V>>
+}
+
+impl<V> RadixNode<V> {
+    fn new(label: Vec<u8>, value: Option<V>) -> Self {
+        RadixNode { label, value, children: BTreeMap::new() }
+    }
+
+    // Merges a node without a value into its only child.
+    fn merge_with_child(&mut self) {
+        let (_, child) = std::mem::take(&mut self.children).pop_first().unwrap();
+        self.label.extend_from_slice(&child.label);
+        self.value = child.value;
+        self.children = child.children;
+    }
+}
+
+/// The Drop trait for the tries. Because these data structures are recursive, the default,
+/// compiler-generated implementation may cause stack overflows for long keys.
+impl<V> Drop for Trie<V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<TrieNode<V>> = std::mem::take(&mut self.root.children).into_values().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(std::mem::take(&mut node.children).into_values());
+        }
+    }
+}
+
+impl<V> Drop for RadixTree<V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<RadixNode<V>> = std::mem::take(&mut self.root.children).into_values().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(std::mem::take(&mut node.children).into_values());
+        }
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Trie<V> {
+    pub fn new() -> Trie<V> {
+        Trie { root: TrieNode::new(), len: 0 }
+    }
+
+    /// Number of keys in the trie.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a value, returning the previous one stored under the key.
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key: K, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for &byte in key.as_ref() {
+            node = node.children.entry(byte).or_insert_with(TrieNode::new);
+        }
+
+        let previous = node.value.replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+
+        previous
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&V> {
+        self.find_node(key.as_ref())?.value.as_ref()
+    }
+
+    pub fn get_mut<K: AsRef<[u8]>>(&mut self, key: K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        for byte in key.as_ref() {
+            node = node.children.get_mut(byte)?;
+        }
+
+        node.value.as_mut()
+    }
+
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes a key, pruning nodes which no longer lead to any value.
+    pub fn remove<K: AsRef<[u8]>>(&mut self, key: K) -> Option<V> {
+        let key = key.as_ref();
+
+        // The deepest node on the path which stays: the root, or one with a value or other children.
+        let mut node = &self.root;
+        let mut kept = 0;
+        for (depth, byte) in key.iter().enumerate() {
+            if node.value.is_some() || node.children.len() > 1 {
+                kept = depth;
+            }
+            node = node.children.get(byte)?;
+        }
+        node.value.as_ref()?;
+        let prune = !key.is_empty() && node.children.is_empty();
+
+        let mut node = &mut self.root;
+        let length = if prune { kept } else { key.len() };
+        for byte in &key[..length] {
+            node = node.children.get_mut(byte).unwrap();
+        }
+
+        let value = if prune {
+            // The pruned nodes form a chain, which is taken apart iteratively like in `drop`.
+            let mut pruned = node.children.remove(&key[kept]).unwrap();
+            while let Some((_, child)) = pruned.children.pop_first() {
+                pruned = child;
+            }
+
+            pruned.value.take()
+        }
+        else {
+            node.value.take()
+        };
+
+        self.len -= 1;
+        value
+    }
+
+    /// Iterates over all entries in lexicographic order of keys.
+    pub fn iter(&self) -> TrieIterator<'_, V> {
+        self.iter_prefix([])
+    }
+
+    /// Iterates, in lexicographic order, over entries whose keys start with the prefix.
+    pub fn iter_prefix<K: AsRef<[u8]>>(&self, prefix: K) -> TrieIterator<'_, V> {
+        let prefix = prefix.as_ref();
+        let stack = match self.find_node(prefix) {
+            Some(node) => vec![(prefix.to_vec(), node)],
+            None => Vec::new()
+        };
+
+        TrieIterator { stack }
+    }
+
+    /// Finds the longest key which is a prefix of the given one, e.g. the most specific
+    /// route for an address. Returns the length of the matched prefix and its value.
+    pub fn longest_prefix_match<K: AsRef<[u8]>>(&self, key: K) -> Option<(usize, &V)> {
+        let mut node = &self.root;
+        let mut result = node.value.as_ref().map(|value| (0, value));
+
+        for (i, byte) in key.as_ref().iter().enumerate() {
+            match node.children.get(byte) {
+                Some(child) => node = child,
+                None => break
+            }
+
+            if let Some(value) = &node.value {
+                result = Some((i + 1, value));
+            }
+        }
+
+        result
+    }
+
+    /// Returns at most `k` entries starting with the prefix, with the highest weights first.
+    /// Entries with equal weights are ordered by key.
+    pub fn top_k<K: AsRef<[u8]>, W: Ord, F: Fn(&V) -> W>(&self, prefix: K, k: usize, weight: F) -> Vec<(Vec<u8>, &V)> {
+        top_k(self.iter_prefix(prefix), k, weight)
+    }
+
+    fn find_node(&self, key: &[u8]) -> Option<&TrieNode<V>> {
+        let mut node = &self.root;
+        for byte in key {
+            node = node.children.get(byte)?;
+        }
+
+        Some(node)
+    }
+}
+
+pub struct TrieIterator<'a, V> {
+    stack: Vec<(Vec<u8>, &'a TrieNode<V>)>
+}
+
+impl<'a, V> Iterator for TrieIterator<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A key is smaller than all of its extensions, so nodes are visited in pre-order.
+        while let Some((key, node)) = self.stack.pop() {
+            for (&byte, child) in node.children.iter().rev() {
+                let mut child_key = key.clone();
+                child_key.push(byte);
+                self.stack.push((child_key, child));
+            }
+
+            if let Some(value) = &node.value {
+                return Some((key, value));
+            }
+        }
+
+        None
+    }
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> RadixTree<V> {
+    pub fn new() -> RadixTree<V> {
+        RadixTree { root: RadixNode::new(Vec::new(), None), len: 0 }
+    }
+
+    /// Number of keys in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a value, returning the previous one stored under the key.
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key: K, value: V) -> Option<V> {
+        let mut key = key.as_ref();
+        let mut node = &mut self.root;
+
+        while let Some(&first) = key.first() {
+            let child = match node.children.entry(first) {
+                Entry::Vacant(entry) => {
+                    entry.insert(RadixNode::new(key.to_vec(), Some(value)));
+                    self.len += 1;
+                    return None;
+                },
+                Entry::Occupied(entry) => entry.into_mut()
+            };
+            let common = common_prefix_length(&child.label, key);
+
+            if common < child.label.len() {
+                // Split the edge, so that the common part ends in a node.
+                let suffix = child.label.split_off(common);
+                let mut lower = RadixNode::new(suffix, child.value.take());
+                lower.children = std::mem::take(&mut child.children);
+                child.children.insert(lower.label[0], lower);
+            }
+
+            key = &key[common..];
+            node = child;
+        }
+
+        let previous = node.value.replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+
+        previous
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&V> {
+        self.find_node(key.as_ref())?.value.as_ref()
+    }
+
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes a key, merging nodes left with a single child and no value.
+    pub fn remove<K: AsRef<[u8]>>(&mut self, key: K) -> Option<V> {
+        let mut key = key.as_ref();
+
+        // First bytes of the edges leading to the node of the key.
+        let mut path = Vec::new();
+        let mut node = &self.root;
+        while let Some(&first) = key.first() {
+            let child = node.children.get(&first)?;
+            if !key.starts_with(&child.label) {
+                return None;
+            }
+
+            path.push(first);
+            key = &key[child.label.len()..];
+            node = child;
+        }
+        node.value.as_ref()?;
+        self.len -= 1;
+
+        // Only the node of the key and its parent can change.
+        let (last, ancestors) = match path.split_last() {
+            Some(split) => split,
+            None => return self.root.value.take()
+        };
+        let mut parent = &mut self.root;
+        for first in ancestors {
+            parent = parent.children.get_mut(first).unwrap();
+        }
+
+        let child = parent.children.get_mut(last).unwrap();
+        let value = child.value.take();
+        match child.children.len() {
+            0 => {
+                parent.children.remove(last);
+                if !ancestors.is_empty() && parent.value.is_none() && parent.children.len() == 1 {
+                    parent.merge_with_child();
+                }
+            },
+            1 => child.merge_with_child(),
+            _ => {}
+        }
+
+        value
+    }
+
+    /// Iterates over all entries in lexicographic order of keys.
+    pub fn iter(&self) -> RadixTreeIterator<'_, V> {
+        self.iter_prefix([])
+    }
+
+    /// Iterates, in lexicographic order, over entries whose keys start with the prefix.
+    pub fn iter_prefix<K: AsRef<[u8]>>(&self, prefix: K) -> RadixTreeIterator<'_, V> {
+        let mut prefix = prefix.as_ref();
+        let mut node = &self.root;
+        let mut key = Vec::new();
+
+        while let Some(first) = prefix.first() {
+            let child = match node.children.get(first) {
+                Some(child) => child,
+                None => return RadixTreeIterator { stack: Vec::new() }
+            };
+
+            // The prefix may end in the middle of an edge.
+            let common = common_prefix_length(&child.label, prefix);
+            if common < prefix.len() && common < child.label.len() {
+                return RadixTreeIterator { stack: Vec::new() };
+            }
+
+            key.extend_from_slice(&child.label);
+            prefix = &prefix[common..];
+            node = child;
+        }
+
+        RadixTreeIterator { stack: vec![(key, node)] }
+    }
+
+    /// Finds the longest key which is a prefix of the given one. Returns the length of the
+    /// matched prefix and its value.
+    pub fn longest_prefix_match<K: AsRef<[u8]>>(&self, key: K) -> Option<(usize, &V)> {
+        let key = key.as_ref();
+        let mut node = &self.root;
+        let mut length = 0;
+        let mut result = node.value.as_ref().map(|value| (0, value));
+
+        while let Some(child) = key.get(length).and_then(|first| node.children.get(first)) {
+            if !key[length..].starts_with(&child.label) {
+                break;
+            }
+
+            length += child.label.len();
+            node = child;
+            if let Some(value) = &node.value {
+                result = Some((length, value));
+            }
+        }
+
+        result
+    }
+
+    /// Returns at most `k` entries starting with the prefix, with the highest weights first.
+    /// Entries with equal weights are ordered by key.
+    pub fn top_k<K: AsRef<[u8]>, W: Ord, F: Fn(&V) -> W>(&self, prefix: K, k: usize, weight: F) -> Vec<(Vec<u8>, &V)> {
+        top_k(self.iter_prefix(prefix), k, weight)
+    }
+
+    fn find_node(&self, mut key: &[u8]) -> Option<&RadixNode<V>> {
+        let mut node = &self.root;
+        while let Some(first) = key.first() {
+            node = node.children.get(first)?;
+            key = key.strip_prefix(node.label.as_slice())?;
+        }
+
+        Some(node)
+    }
+}
+
+pub struct RadixTreeIterator<'a, V> {
+    stack: Vec<(Vec<u8>, &'a RadixNode<V>)>
+}
+
+impl<'a, V> Iterator for RadixTreeIterator<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            for child in node.children.values().rev() {
+                let mut child_key = key.clone();
+                child_key.extend_from_slice(&child.label);
+                self.stack.push((child_key, child));
+            }
+
+            if let Some(value) = &node.value {
+                return Some((key, value));
+            }
+        }
+
+        None
+    }
+}
+
+fn common_prefix_length(first: &[u8], second: &[u8]) -> usize {
+    first.iter().zip(second).take_while(|(a, b)| a == b).count()
+}
+
+// Ordered by weight, then by key in the reversed order, so that the heap top is the entry
+// to be dropped first.
+struct Candidate<'a, W, V> {
+    weight: W,
+    key: Vec<u8>,
+    value: &'a V
+}
+
+impl<'a, W: Ord, V> Ord for Candidate<'a, W, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.weight.cmp(&other.weight).then_with(|| other.key.cmp(&self.key))
+    }
+}
+
+impl<'a, W: Ord, V> PartialOrd for Candidate<'a, W, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, W: Ord, V> PartialEq for Candidate<'a, W, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, W: Ord, V> Eq for Candidate<'a, W, V> {}
+
+fn top_k<'a, V: 'a, W: Ord, I, F>(entries: I, k: usize, weight: F) -> Vec<(Vec<u8>, &'a V)>
+    where I: Iterator<Item = (Vec<u8>, &'a V)>, F: Fn(&V) -> W {
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+
+    for (key, value) in entries {
+        heap.push(Reverse(Candidate { weight: weight(value), key, value }));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+
+    heap.into_sorted_vec().into_iter()
+        .map(|Reverse(candidate)| (candidate.key, candidate.value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn trie_test() {
+        let mut trie = Trie::new();
+        assert_eq!(None, trie.insert("tea", 3));
+        assert_eq!(None, trie.insert("ten", 12));
+        assert_eq!(None, trie.insert("to", 7));
+        assert_eq!(None, trie.insert("inn", 9));
+        assert_eq!(Some(3), trie.insert("tea", 4));
+
+        assert_eq!(4, trie.len());
+        assert_eq!(Some(&4), trie.get("tea"));
+        assert!(!trie.contains_key("te"));
+        assert_eq!(
+            vec![(b"tea".to_vec(), &4), (b"ten".to_vec(), &12)],
+            trie.iter_prefix("te").collect::<Vec<_>>());
+
+        assert_eq!(Some(12), trie.remove("ten"));
+        assert_eq!(None, trie.remove("ten"));
+        assert_eq!(None, trie.remove("te"));
+        assert_eq!(3, trie.len());
+        assert_eq!(Some(&4), trie.get("tea"));
+    }
+
+    #[test]
+    fn longest_prefix_match_test() {
+        let mut trie = Trie::new();
+        let mut radix = RadixTree::new();
+        for (route, name) in [("/", "root"), ("/api/", "api"), ("/api/v2/", "v2")] {
+            trie.insert(route, name);
+            radix.insert(route, name);
+        }
+
+        assert_eq!(Some((8, &"v2")), trie.longest_prefix_match("/api/v2/users"));
+        assert_eq!(Some((5, &"api")), trie.longest_prefix_match("/api/v1/users"));
+        assert_eq!(Some((1, &"root")), trie.longest_prefix_match("/static"));
+        assert_eq!(None, trie.longest_prefix_match("static"));
+
+        assert_eq!(Some((8, &"v2")), radix.longest_prefix_match("/api/v2/users"));
+        assert_eq!(Some((5, &"api")), radix.longest_prefix_match("/api/v1/users"));
+        assert_eq!(Some((1, &"root")), radix.longest_prefix_match("/static"));
+        assert_eq!(None, radix.longest_prefix_match("static"));
+    }
+
+    #[test]
+    fn top_k_test() {
+        let mut trie = Trie::new();
+        let mut radix = RadixTree::new();
+        for (command, uses) in [("commit", 50), ("checkout", 30), ("cherry-pick", 5), ("clone", 30), ("add", 100)] {
+            trie.insert(command, uses);
+            radix.insert(command, uses);
+        }
+
+        let expected = vec![(b"commit".to_vec(), &50), (b"checkout".to_vec(), &30), (b"clone".to_vec(), &30)];
+        assert_eq!(expected, trie.top_k("c", 3, |&uses| uses));
+        assert_eq!(expected, radix.top_k("c", 3, |&uses| uses));
+        assert!(trie.top_k("x", 3, |&uses| uses).is_empty());
+    }
+
+    #[test]
+    fn radix_tree_edges_test() {
+        let mut radix = RadixTree::new();
+        radix.insert("romane", 1);
+        radix.insert("romanus", 2);
+        radix.insert("romulus", 3);
+        radix.insert("rom", 4);
+
+        assert_eq!(
+            vec![(b"romane".to_vec(), &1), (b"romanus".to_vec(), &2)],
+            radix.iter_prefix("roma").collect::<Vec<_>>());
+        assert_eq!(4, radix.iter_prefix("ro").count());
+        assert_eq!(0, radix.iter_prefix("romx").count());
+
+        assert_eq!(Some(4), radix.remove("rom"));
+        assert_eq!(None, radix.remove("roman"));
+        assert_eq!(Some(&2), radix.get("romanus"));
+        assert!(!radix.contains_key("roman"));
+    }
+
+    #[test]
+    fn randomized_test() {
+        let mut rng = StdRng::seed_from_u64(37);
+        let mut trie = Trie::new();
+        let mut radix = RadixTree::new();
+        let mut expected = BTreeMap::new();
+
+        for i in 0..3000 {
+            let key: Vec<u8> = (0..rng.gen_range(0..6)).map(|_| rng.gen_range(b'a'..b'd')).collect();
+            if rng.gen_bool(0.6) {
+                let previous = expected.insert(key.clone(), i);
+                assert_eq!(previous, trie.insert(&key, i));
+                assert_eq!(previous, radix.insert(&key, i));
+            }
+            else {
+                let removed = expected.remove(&key);
+                assert_eq!(removed, trie.remove(&key));
+                assert_eq!(removed, radix.remove(&key));
+            }
+
+            assert_eq!(expected.len(), trie.len());
+            assert_eq!(expected.len(), radix.len());
+        }
+
+        let expected: Vec<(Vec<u8>, &i32)> = expected.iter().map(|(key, value)| (key.clone(), value)).collect();
+        assert_eq!(expected, trie.iter().collect::<Vec<_>>());
+        assert_eq!(expected, radix.iter().collect::<Vec<_>>());
+
+        let with_prefix: Vec<(Vec<u8>, &i32)> = expected.iter().filter(|(key, _)| key.starts_with(b"ab")).cloned().collect();
+        assert_eq!(with_prefix, trie.iter_prefix("ab").collect::<Vec<_>>());
+        assert_eq!(with_prefix, radix.iter_prefix("ab").collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn long_key_test() {
+        let mut trie = Trie::new();
+        trie.insert(vec![b'a'; 200_000], 1);
+        assert_eq!(Some(&1), trie.get(vec![b'a'; 200_000]));
+
+        trie.insert(vec![b'a'; 1000], 2);
+        assert_eq!(Some(1), trie.remove(vec![b'a'; 200_000]));
+        assert_eq!(None, trie.get(vec![b'a'; 200_000]));
+        assert_eq!(Some(&2), trie.get(vec![b'a'; 1000]));
+        assert_eq!(1, trie.len());
+
+        trie.insert(vec![b'b'; 200_000], 3);
+        assert_eq!(Some(3), trie.remove(vec![b'b'; 200_000]));
+        assert_eq!(vec![(vec![b'a'; 1000], &2)], trie.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn radix_tree_deep_remove_test() {
+        let depth = 5_000;
+        let mut tree = RadixTree::new();
+        for length in 1..=depth {
+            tree.insert(vec![b'a'; length], length);
+        }
+
+        assert_eq!(Some(depth), tree.remove(vec![b'a'; depth]));
+        assert_eq!(Some(1), tree.remove([b'a']));
+        assert_eq!(Some(depth / 2), tree.remove(vec![b'a'; depth / 2]));
+        assert_eq!(None, tree.remove(vec![b'a'; depth / 2]));
+        assert_eq!(depth - 3, tree.len());
+        assert_eq!(Some(&(depth - 1)), tree.get(vec![b'a'; depth - 1]));
+        assert_eq!(None, tree.get(vec![b'a'; depth / 2]));
+        assert_eq!(depth - 3, tree.iter().count());
+    }
+}
@@ -0,0 +1,101 @@
+#!/usr/bin/env python3
+"""Generates src/patterns/unicode/tables.rs from the Unicode database bundled with Python.
+
+Usage: python3 tools/unicode_tables.py > src/patterns/unicode/tables.rs
+"""
+import sys
+import unicodedata
+
+HANGUL_START, HANGUL_END = 0xAC00, 0xD7A3
+
+
+def scalar_values():
+    for code in range(0x110000):
+        if 0xD800 <= code <= 0xDFFF:
+            continue
+        yield code, chr(code)
+
+
+def rust_char(c):
+    code = ord(c)
+    if 0x20 < code < 0x7F and c not in "'\\":
+        return "'%s'" % c
+    return "'\\u{%x}'" % code
+
+
+def rust_str(s):
+    return '"%s"' % "".join("\\u{%x}" % ord(c) for c in s)
+
+
+def main():
+    decompositions = []
+    combining_classes = []
+    compositions = []
+    full_folding = []
+    simple_folding = []
+
+    for code, c in scalar_values():
+        if HANGUL_START <= code <= HANGUL_END:
+            continue
+
+        decomposition = unicodedata.normalize("NFD", c)
+        if decomposition != c:
+            decompositions.append((c, decomposition))
+
+        raw = unicodedata.decomposition(c)
+        if raw and not raw.startswith("<"):
+            parts = [chr(int(part, 16)) for part in raw.split()]
+            # Singletons, non-starter decompositions and exclusions do not recompose.
+            if len(parts) == 2 and unicodedata.normalize("NFC", "".join(parts)) == c:
+                compositions.append((parts[0], parts[1], c))
+
+        combining_class = unicodedata.combining(c)
+        if combining_class:
+            combining_classes.append((c, combining_class))
+
+        folded = c.casefold()
+        if folded != c:
+            full_folding.append((c, folded))
+            if len(folded) == 1:
+                simple_folding.append((c, folded))
+            elif len(c.lower()) == 1 and c.lower() != c:
+                simple_folding.append((c, c.lower()))
+
+    compositions.sort()
+    out = sys.stdout
+    out.write("// Generated by tools/unicode_tables.py from Unicode %s data. Do not edit.\n\n" % unicodedata.unidata_version)
+    out.write("pub const UNICODE_VERSION: &str = \"%s\";\n\n" % unicodedata.unidata_version)
+
+    out.write("// Full canonical decompositions (Hangul syllables are decomposed algorithmically).\n")
+    out.write("pub const DECOMPOSITIONS: &[(char, &str)] = &[\n")
+    for c, decomposition in decompositions:
+        out.write("    (%s, %s),\n" % (rust_char(c), rust_str(decomposition)))
+    out.write("];\n\n")
+
+    out.write("// Canonical combining classes different from 0.\n")
+    out.write("pub const COMBINING_CLASSES: &[(char, u8)] = &[\n")
+    for c, combining_class in combining_classes:
+        out.write("    (%s, %d),\n" % (rust_char(c), combining_class))
+    out.write("];\n\n")
+
+    out.write("// Primary composites, sorted by the pair of characters.\n")
+    out.write("pub const COMPOSITIONS: &[((char, char), char)] = &[\n")
+    for first, second, c in compositions:
+        out.write("    ((%s, %s), %s),\n" % (rust_char(first), rust_char(second), rust_char(c)))
+    out.write("];\n\n")
+
+    out.write("// Case folding with status C and S.\n")
+    out.write("pub const SIMPLE_CASE_FOLDING: &[(char, char)] = &[\n")
+    for c, folded in simple_folding:
+        out.write("    (%s, %s),\n" % (rust_char(c), rust_char(folded)))
+    out.write("];\n\n")
+
+    out.write("// Case folding with status C and F.\n")
+    out.write("pub const FULL_CASE_FOLDING: &[(char, &str)] = &[\n")
+    for c, folded in full_folding:
+        out.write("    (%s, %s),\n" % (rust_char(c), rust_str(folded)))
+    out.write("];\n")
+
+
+if __name__ == "__main__":
+    main()